@@ -22,7 +22,9 @@ function createEffect(config?: RainbowEffectConfig): EffectMachine {
       const buf = m.leds;
       for (let i = 0; i < ledCount; i++) {
         const hue = (i * hueSpread + offset) % 360;
-        const rgb = hsvToRgb(hue, saturation, brightness);
+        // Accepted: one short-lived [r, g, b] per LED per tick, since the runtime
+        // has no out-parameter HSV conversion yet.
+        const rgb = hsvToRgb(hue, saturation, brightness); // webf-effects-mcu:ignore
         const o = i * 3;
         buf[o] = rgb[0]; buf[o + 1] = rgb[1]; buf[o + 2] = rgb[2];
       }
@@ -5,7 +5,17 @@
 //! anyhow = "1"
 //! regex = "1"
 //! walkdir = "2"
+//! globset = "0.4"
+//! serde = { version = "1", features = ["derive"] }
+//! serde_json = "1"
+//! json5 = "0.4"
 //! clap = { version = "4", features = ["derive"] }
+//! oxc_allocator = "0.110"
+//! oxc_ast = "0.110"
+//! oxc_ast_visit = "0.110"
+//! oxc_parser = "0.110"
+//! oxc_span = "0.110"
+//! oxc_syntax = "0.110"
 //! ```
 
 use anyhow::{anyhow, Context, Result};
@@ -14,188 +24,5571 @@ use std::{
     collections::{BTreeMap, BTreeSet},
     fs,
     path::{Path, PathBuf},
+    sync::LazyLock,
 };
 use walkdir::WalkDir;
 use clap::{Parser, ValueEnum};
+use serde::Deserialize;
+use oxc_allocator::Allocator;
+use oxc_ast::ast::{
+    ArrayExpressionElement, ArrowFunctionExpression, AssignmentExpression, AssignmentOperator, AwaitExpression, BigIntLiteral,
+    BinaryExpression, BinaryOperator, BindingIdentifier, CallExpression, CatchClause, ChainExpression, Class,
+    Expression, ForOfStatement, Function, FunctionBody, IdentifierReference, ImportExpression, LogicalExpression,
+    LogicalOperator, MetaProperty, NumericLiteral, ObjectExpression, ObjectProperty, ObjectPropertyKind,
+    PrivateIdentifier, PrivateInExpression, PropertyDefinition, PropertyKey, RegExpFlags, RegExpLiteral, Statement, StaticBlock,
+    SwitchStatement, TSGlobalDeclaration, TSInterfaceDeclaration, TSSignature, TSType, VariableDeclarator,
+};
+use oxc_ast_visit::{walk, Visit};
+use oxc_parser::Parser as JsParser;
+use oxc_span::SourceType;
+use oxc_syntax::scope::ScopeFlags;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
 enum OnlyMode {
+    /// The baseline checks (`tailwind`, `css-props`).
     All,
+    /// Every check, including the opt-in ones.
+    Strict,
     Tailwind,
     CssProps,
+    DeadClasses,
+    TailwindConfig,
+    WebApis,
+    NativeModules,
+    Protocol,
+    Jsx,
+    Canvas,
+    EsSyntax,
+    Effects,
+    EffectsMcu,
+}
+
+/// Checks run by default (`--only all`); the others are opt-in, one at a time
+/// with `--only <check>` or together with `--only strict`.
+const BASELINE_CHECKS: &[OnlyMode] = &[OnlyMode::Tailwind, OnlyMode::CssProps];
+
+impl OnlyMode {
+    /// Whether running with `--only self` should include `mode`.
+    fn includes(self, mode: OnlyMode) -> bool {
+        match self {
+            OnlyMode::All => BASELINE_CHECKS.contains(&mode),
+            OnlyMode::Strict => true,
+            _ => self == mode,
+        }
+    }
 }
 
 #[derive(Parser, Debug)]
 #[command(name = "check-webf-constraints")]
 #[command(about = "Checks WebF constraints: Tailwind blacklist + CSS property whitelist")]
 struct Cli {
-    /// Only run a specific check.
+    /// Only run a specific check. `all` runs the baseline checks (tailwind,
+    /// css-props); `strict` also runs every opt-in check.
     #[arg(long, value_enum, default_value = "all")]
     only: OnlyMode,
 
-    /// Also scan source files (tsx/jsx/ts/js) for unsupported properties.
-    #[arg(long, alias = "scan_source")]
-    scan_source: bool,
-}
+    /// Also scan source files (tsx/jsx/ts/js) for unsupported properties.
+    #[arg(long, alias = "scan_source")]
+    scan_source: bool,
+
+    /// Source directory to scan, relative to the frontend root (repeatable).
+    /// Defaults to the `content` globs in tailwind.config.cjs.
+    #[arg(long = "scan", value_name = "DIR")]
+    scan: Vec<PathBuf>,
+
+    /// Project root to check (a directory with package.json), e.g.
+    /// `contrib/webf_usecases/vue_usecases`. Defaults to the `frontend/` app.
+    #[arg(long, value_name = "DIR", conflicts_with = "workspace")]
+    root: Option<PathBuf>,
+
+    /// Check every project listed in a workspace manifest and print a combined
+    /// report (default manifest: webf-check.workspace.json in the repo root).
+    #[arg(
+        long,
+        value_name = "FILE",
+        num_args = 0..=1,
+        default_missing_value = WORKSPACE_MANIFEST,
+        conflicts_with = "scan"
+    )]
+    workspace: Option<PathBuf>,
+
+    /// Extra WebF guard (repeatable) besides `isWebFEnvironment`; code it
+    /// excludes, e.g. `if (!guard) { ... }`, is browser-only.
+    #[arg(long = "browser-guard", value_name = "NAME")]
+    browser_guard: Vec<String>,
+
+    /// How to handle violations in browser-only code.
+    #[arg(long, value_enum, default_value = "warn")]
+    browser_only: BrowserOnlyMode,
+
+    /// JS engine profile from docs/js_engine.json5 for the dist syntax check.
+    #[arg(long, value_name = "PROFILE")]
+    js_engine: Option<String>,
+
+    /// Compile target from docs/effects_mcu.json5 for the effect MCU lint
+    /// (matches `just compile-effects <target>`).
+    #[arg(long, value_name = "TARGET")]
+    mcu_target: Option<String>,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let cwd = std::env::current_dir().context("get current working directory")?;
+    let frontend_root = match cli.root.as_deref() {
+        Some(root) => explicit_project_root(&cwd, root)?,
+        None => locate_frontend_root(&cwd)?,
+    };
+    let repo_root = locate_repo_root(&frontend_root)?;
+
+    if let Some(manifest) = cli.workspace.as_deref() {
+        let manifest_path = if cwd.join(manifest).is_file() {
+            cwd.join(manifest)
+        } else {
+            repo_root.join(manifest)
+        };
+        if !run_workspace(&manifest_path, &repo_root, &cli)? {
+            std::process::exit(1);
+        }
+        println!("[webf-check] OK");
+        return Ok(());
+    }
+
+    let allow_packages = manifest_allow_packages(&repo_root, &frontend_root)?;
+    let project = Project {
+        name: frontend_root
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        dist: Some(frontend_root.join("dist")),
+        root: frontend_root,
+        sources: cli.scan.clone(),
+        checks: OnlyMode::value_variants()
+            .iter()
+            .copied()
+            .filter(|m| !matches!(m, OnlyMode::All | OnlyMode::Strict))
+            .collect(),
+        scan_source: cli.scan_source,
+        static_html: cli.root.is_none(),
+        allow_packages,
+        browser_guards: BrowserGuards::new(&cli.browser_guard, cli.browser_only),
+        js_engine: cli.js_engine.clone(),
+        mcu_target: cli.mcu_target.clone(),
+    };
+
+    if !run_project(&project, &repo_root, cli.only, cli.scan_source)?.is_empty() {
+        std::process::exit(1);
+    }
+
+    println!("[webf-check] OK");
+    Ok(())
+}
+
+
+/// Default workspace manifest, relative to the repo root.
+const WORKSPACE_MANIFEST: &str = "webf-check.workspace.json";
+
+/// Workspace manifest listing every WebF bundle checked in one run.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WorkspaceManifest {
+    projects: Vec<WorkspaceProject>,
+    /// Extra or overridden rule profiles; `app` and `library` are built in.
+    #[serde(default)]
+    profiles: BTreeMap<String, RuleProfile>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WorkspaceProject {
+    name: String,
+    /// Project root, relative to the repo root.
+    root: PathBuf,
+    /// Build output relative to `root`; `null` for source-only projects.
+    #[serde(default = "default_dist_dir")]
+    dist: Option<PathBuf>,
+    /// Source dirs relative to `root`; defaults to the Tailwind `content` globs.
+    #[serde(default)]
+    sources: Vec<PathBuf>,
+    #[serde(default = "default_profile_name")]
+    profile: String,
+    /// Skip (instead of failing) when `root` is missing, e.g. an uninitialized submodule.
+    #[serde(default)]
+    optional: bool,
+    /// Also scan static HTML under the repo `assets/` dir.
+    #[serde(default)]
+    static_html: bool,
+    /// Unsupported dist CSS properties accepted per npm package (`"*"` for all).
+    #[serde(default)]
+    allow_packages: BTreeMap<String, Vec<String>>,
+    /// Extra WebF guard names, like `isWebFEnvironment`, for browser-only code.
+    #[serde(default)]
+    browser_guards: Vec<String>,
+}
+
+/// Which checks run for a project.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleProfile {
+    /// `--only` names, e.g. `["tailwind", "css-props"]`.
+    checks: Vec<String>,
+    /// Run the source-level CSS property scan (implied when there is no dist).
+    #[serde(default)]
+    scan_source: bool,
+}
+
+fn default_dist_dir() -> Option<PathBuf> {
+    Some(PathBuf::from("dist"))
+}
+
+fn default_profile_name() -> String {
+    "app".to_string()
+}
+
+fn builtin_profiles() -> BTreeMap<String, RuleProfile> {
+    let profile = |checks: &[&str], scan_source| RuleProfile {
+        checks: checks.iter().map(|c| c.to_string()).collect(),
+        scan_source,
+    };
+    BTreeMap::from([
+        (
+            "app".to_string(),
+            profile(
+                &["tailwind", "tailwind-config", "dead-classes", "css-props", "web-apis", "native-modules", "protocol", "jsx", "canvas", "es-syntax", "effects", "effects-mcu"],
+                true,
+            ),
+        ),
+        ("library".to_string(), profile(&["tailwind", "css-props", "web-apis", "jsx"], true)),
+    ])
+}
+
+/// One project checked in a run: the `frontend/` app by default, or an entry
+/// of the workspace manifest.
+#[derive(Debug)]
+struct Project {
+    name: String,
+    root: PathBuf,
+    dist: Option<PathBuf>,
+    sources: Vec<PathBuf>,
+    checks: Vec<OnlyMode>,
+    scan_source: bool,
+    static_html: bool,
+    allow_packages: BTreeMap<String, Vec<String>>,
+    browser_guards: BrowserGuards,
+    /// Engine profile name for `es-syntax`; `None` uses the data file default.
+    js_engine: Option<String>,
+    /// Compile target for `effects-mcu`; `None` uses the data file default.
+    mcu_target: Option<String>,
+}
+
+impl Project {
+    fn runs(&self, only: OnlyMode, mode: OnlyMode) -> bool {
+        only.includes(mode) && self.checks.contains(&mode)
+    }
+}
+
+/// Guards that are true only inside WebF; code they exclude never runs on device.
+const DEFAULT_BROWSER_GUARDS: &[&str] = &["isWebFEnvironment"];
+
+/// How violations in browser-only code (excluded by a WebF guard) are handled.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+enum BrowserOnlyMode {
+    /// Print them as warnings that do not fail the check.
+    #[default]
+    Warn,
+    /// Drop them silently.
+    Suppress,
+    /// Treat them like any other violation.
+    Report,
+}
+
+/// WebF guard names plus the policy for the code they exclude.
+#[derive(Debug, Clone)]
+struct BrowserGuards {
+    names: Vec<String>,
+    mode: BrowserOnlyMode,
+}
+
+impl BrowserGuards {
+    fn new(extra: &[String], mode: BrowserOnlyMode) -> Self {
+        let mut names: Vec<String> = DEFAULT_BROWSER_GUARDS.iter().map(|g| g.to_string()).collect();
+        for name in extra {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        Self { names, mode }
+    }
+
+    /// Browser-only byte ranges of a script file; empty in `report` mode.
+    fn ranges(&self, path: &Path, text: &str) -> Vec<(usize, usize)> {
+        if self.mode == BrowserOnlyMode::Report {
+            return Vec::new();
+        }
+        let mut ranges = Vec::new();
+        for (start, end) in script_ranges(path, text) {
+            let code = mask_js_non_code(&text[start..end]);
+            ranges.extend(
+                guarded_browser_ranges(&code, &self.names)
+                    .into_iter()
+                    .map(|(a, b)| (start + a, start + b)),
+            );
+        }
+        ranges
+    }
+
+    /// Print violations found in browser-only code (only collected in `warn` mode).
+    fn print_warnings(&self, tag: &str, lines: &[String]) {
+        if lines.is_empty() {
+            return;
+        }
+        eprintln!(
+            "[{tag}] warning: {} in browser-only code (guarded by {}), not failing:",
+            lines.len(),
+            self.names.join(", ")
+        );
+        for line in lines.iter().take(30) {
+            eprintln!("{line}");
+        }
+        if lines.len() > 30 {
+            eprintln!("...and {} more", lines.len() - 30);
+        }
+    }
+}
+
+fn in_ranges(ranges: &[(usize, usize)], offset: usize) -> bool {
+    ranges.iter().any(|&(start, end)| (start..end).contains(&offset))
+}
+
+/// A manifest entry after resolution: either a runnable project or an optional
+/// one whose root is absent.
+enum WorkspaceEntry {
+    Project(Project),
+    Missing { name: String, reason: String },
+}
+
+/// Outcome of one project in a workspace run.
+enum ProjectOutcome {
+    Passed,
+    Failed(Vec<OnlyMode>),
+    Skipped(String),
+}
+
+fn load_workspace(manifest_path: &Path, repo_root: &Path) -> Result<Vec<WorkspaceEntry>> {
+    let raw = fs::read_to_string(manifest_path)
+        .with_context(|| format!("read {}", manifest_path.display()))?;
+    let manifest: WorkspaceManifest = serde_json::from_str(&raw)
+        .with_context(|| format!("parse {}", manifest_path.display()))?;
+
+    let mut profiles = builtin_profiles();
+    profiles.extend(manifest.profiles);
+
+    let mut projects = Vec::new();
+    for entry in manifest.projects {
+        let profile = profiles.get(&entry.profile).ok_or_else(|| {
+            anyhow!("project `{}` uses unknown profile `{}`", entry.name, entry.profile)
+        })?;
+        let checks = profile
+            .checks
+            .iter()
+            .map(|c| OnlyMode::from_str(c, false).map_err(|e| anyhow!("profile `{}`: {e}", entry.profile)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let root = repo_root.join(&entry.root);
+        if !root.is_dir() {
+            let reason = format!("root not found: {}", entry.root.display());
+            if entry.optional {
+                projects.push(WorkspaceEntry::Missing { name: entry.name, reason });
+                continue;
+            }
+            return Err(anyhow!("project `{}`: {reason}", entry.name));
+        }
+
+        projects.push(WorkspaceEntry::Project(Project {
+            name: entry.name,
+            dist: entry.dist.map(|d| root.join(d)),
+            root,
+            sources: entry.sources,
+            checks,
+            scan_source: profile.scan_source,
+            static_html: entry.static_html,
+            allow_packages: entry.allow_packages,
+            browser_guards: BrowserGuards::new(&entry.browser_guards, BrowserOnlyMode::default()),
+            js_engine: None,
+            mcu_target: None,
+        }));
+    }
+
+    Ok(projects)
+}
+
+/// `allow_packages` of the workspace manifest entry for `project_root`, so a
+/// single-project run honours the same per-package allow entries.
+fn manifest_allow_packages(repo_root: &Path, project_root: &Path) -> Result<BTreeMap<String, Vec<String>>> {
+    let manifest_path = repo_root.join(WORKSPACE_MANIFEST);
+    if !manifest_path.is_file() {
+        return Ok(BTreeMap::new());
+    }
+    let raw = fs::read_to_string(&manifest_path)
+        .with_context(|| format!("read {}", manifest_path.display()))?;
+    let manifest: WorkspaceManifest = serde_json::from_str(&raw)
+        .with_context(|| format!("parse {}", manifest_path.display()))?;
+
+    let canonical = |p: &Path| fs::canonicalize(p).unwrap_or_else(|_| p.to_path_buf());
+    let project_root = canonical(project_root);
+    Ok(manifest
+        .projects
+        .into_iter()
+        .find(|p| canonical(&repo_root.join(&p.root)) == project_root)
+        .map(|p| p.allow_packages)
+        .unwrap_or_default())
+}
+
+/// Run the selected checks for one project; returns the checks that failed.
+fn run_project(project: &Project, repo_root: &Path, only: OnlyMode, scan_source: bool) -> Result<Vec<OnlyMode>> {
+    let root = &project.root;
+    // Only the Tailwind checks read the config; the other modes scan the fallback dirs.
+    let tailwind = if [OnlyMode::Tailwind, OnlyMode::TailwindConfig, OnlyMode::DeadClasses]
+        .into_iter()
+        .any(|mode| project.runs(only, mode))
+    {
+        TailwindConfig::load(root)
+    } else {
+        TailwindConfig::default()
+    };
+    let scan_files = ScanFiles::resolve(root, repo_root, &tailwind, &project.sources, project.static_html)?;
+
+    let mut failed = Vec::new();
+
+    if project.runs(only, OnlyMode::Tailwind) && !check_tailwind_blacklist(root, &scan_files, &project.browser_guards)? {
+        failed.push(OnlyMode::Tailwind);
+    }
+
+    if project.runs(only, OnlyMode::TailwindConfig) && !check_tailwind_config(root, &tailwind)? {
+        failed.push(OnlyMode::TailwindConfig);
+    }
+
+    if project.runs(only, OnlyMode::DeadClasses) {
+        match project.dist.as_deref() {
+            Some(dist) => {
+                if !check_tailwind_dead_classes(root, dist, &tailwind, &scan_files)? {
+                    failed.push(OnlyMode::DeadClasses);
+                }
+            }
+            None => println!("[webf-dead-classes] skipped: project has no dist output"),
+        }
+    }
+
+    if project.runs(only, OnlyMode::CssProps)
+        && !check_css_properties_with_source_flag(
+            root,
+            project.dist.as_deref(),
+            repo_root,
+            scan_source || project.scan_source,
+            &scan_files,
+            &project.allow_packages,
+            &project.browser_guards,
+        )?
+    {
+        failed.push(OnlyMode::CssProps);
+    }
+
+    if project.runs(only, OnlyMode::WebApis) && !check_web_apis(root, repo_root, &scan_files, &project.browser_guards)? {
+        failed.push(OnlyMode::WebApis);
+    }
+
+    if project.runs(only, OnlyMode::Jsx)
+        && !check_jsx_elements(root, repo_root, &scan_files, &project.browser_guards)?
+    {
+        failed.push(OnlyMode::Jsx);
+    }
+
+    if project.runs(only, OnlyMode::Canvas)
+        && !check_canvas_2d(root, repo_root, &scan_files, &project.browser_guards)?
+    {
+        failed.push(OnlyMode::Canvas);
+    }
+
+    if project.runs(only, OnlyMode::EsSyntax) {
+        match project.dist.as_deref() {
+            Some(dist) => {
+                if !check_dist_es_syntax(root, dist, repo_root, project.js_engine.as_deref())? {
+                    failed.push(OnlyMode::EsSyntax);
+                }
+            }
+            None => println!("[webf-es-syntax] skipped: project has no dist output"),
+        }
+    }
+
+    if project.runs(only, OnlyMode::Effects) && !check_effects_catalog(root)? {
+        failed.push(OnlyMode::Effects);
+    }
+
+    if project.runs(only, OnlyMode::EffectsMcu)
+        && !check_effects_mcu(root, repo_root, project.mcu_target.as_deref())?
+    {
+        failed.push(OnlyMode::EffectsMcu);
+    }
+
+    if project.runs(only, OnlyMode::NativeModules) && !check_native_modules(root, repo_root, &scan_files)? {
+        failed.push(OnlyMode::NativeModules);
+    }
+
+    if project.runs(only, OnlyMode::Protocol) && !check_protocol(root, repo_root, &scan_files)? {
+        failed.push(OnlyMode::Protocol);
+    }
+
+    Ok(failed)
+}
+
+fn run_workspace(manifest_path: &Path, repo_root: &Path, cli: &Cli) -> Result<bool> {
+    let projects = load_workspace(manifest_path, repo_root)?;
+
+    let mut outcomes: Vec<(String, ProjectOutcome)> = Vec::new();
+    for entry in projects {
+        let mut project = match entry {
+            WorkspaceEntry::Project(project) => project,
+            WorkspaceEntry::Missing { name, reason } => {
+                println!("\n[webf-workspace] == {name} == skipped ({reason})");
+                outcomes.push((name, ProjectOutcome::Skipped(reason)));
+                continue;
+            }
+        };
+
+        let extra_guards = [project.browser_guards.names.clone(), cli.browser_guard.clone()].concat();
+        project.browser_guards = BrowserGuards::new(&extra_guards, cli.browser_only);
+        project.js_engine = cli.js_engine.clone();
+        project.mcu_target = cli.mcu_target.clone();
+
+        println!(
+            "\n[webf-workspace] == {} ({}) ==",
+            project.name,
+            pathdiff(repo_root, &project.root)
+        );
+        let failed = run_project(&project, repo_root, cli.only, cli.scan_source)?;
+        let outcome = if failed.is_empty() {
+            ProjectOutcome::Passed
+        } else {
+            ProjectOutcome::Failed(failed)
+        };
+        outcomes.push((project.name, outcome));
+    }
+
+    let width = outcomes.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    let mut ok = true;
+    println!("\n[webf-workspace] Summary:");
+    for (name, outcome) in outcomes.iter() {
+        match outcome {
+            ProjectOutcome::Passed => println!("- {name:<width$}  OK"),
+            ProjectOutcome::Skipped(reason) => println!("- {name:<width$}  SKIPPED ({reason})"),
+            ProjectOutcome::Failed(checks) => {
+                ok = false;
+                let names: Vec<String> = checks
+                    .iter()
+                    .filter_map(|c| c.to_possible_value().map(|v| v.get_name().to_string()))
+                    .collect();
+                println!("- {name:<width$}  FAILED ({})", names.join(", "));
+            }
+        }
+    }
+
+    Ok(ok)
+}
+
+/// `docs/jsx_elements.json5`: allowlists of intrinsic JSX elements, attributes
+/// and DOM event props.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsxSupportList {
+    elements: BTreeMap<String, ApiSupport>,
+    attributes: BTreeMap<String, ApiSupport>,
+    events: BTreeMap<String, ApiSupport>,
+}
+
+impl JsxSupportList {
+    fn load(repo_root: &Path) -> Result<Self> {
+        let path = repo_root.join("docs").join("jsx_elements.json5");
+        let raw = fs::read_to_string(&path).with_context(|| format!("read {}", path.display()))?;
+        json5::from_str(&raw).with_context(|| format!("parse {}", path.display()))
+    }
+
+    /// Why an attribute or event prop is unsupported, if it is.
+    fn attribute_problem(&self, name: &str) -> Option<String> {
+        let is_event = name.len() > 2 && name.starts_with("on") && name[2..].starts_with(|c: char| c.is_ascii_uppercase());
+        if is_event {
+            allowlist_problem(&self.events, name.strip_suffix("Capture").unwrap_or(name))
+        } else {
+            allowlist_problem(&self.attributes, name)
+        }
+    }
+}
+
+/// Why `name` is not allowed by an allowlist with `prefix*` keys, if it is not.
+fn allowlist_problem(list: &BTreeMap<String, ApiSupport>, name: &str) -> Option<String> {
+    let support = list.get(name).or_else(|| {
+        list.iter()
+            .filter_map(|(key, support)| key.strip_suffix('*').map(|prefix| (prefix, support)))
+            .filter(|(prefix, _)| name.starts_with(prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, support)| support)
+    });
+    match support {
+        Some(support) => support.missing_reason().map(str::to_string),
+        None => Some("not in the WebF allowlist".to_string()),
+    }
+}
+
+fn check_jsx_elements(
+    frontend_root: &Path,
+    repo_root: &Path,
+    scan_files: &ScanFiles,
+    guards: &BrowserGuards,
+) -> Result<bool> {
+    let support = JsxSupportList::load(repo_root)?;
+
+    let mut hits: Vec<(String, usize, usize, String, String)> = Vec::new();
+    // Hits in code excluded by a WebF guard.
+    let mut browser_only: Vec<(String, usize, usize, String, String)> = Vec::new();
+
+    for path in scan_files.with_extensions(&["tsx", "jsx"]) {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("read {}", path.display()))?;
+        if text.contains("webf-jsx:disable") {
+            continue;
+        }
+
+        let rel = pathdiff(frontend_root, path);
+        let guarded = guards.ranges(path, &text);
+        let code = mask_js_non_code(&text);
+
+        let mut file_hits: Vec<(usize, String, String)> = Vec::new();
+        for element in extract_jsx_elements(&code) {
+            // Components and custom elements (`<webf-listview>`) are not checked.
+            if !element.name.starts_with(|c: char| c.is_ascii_lowercase()) || element.name.contains(['-', '.']) {
+                continue;
+            }
+            if let Some(reason) = allowlist_problem(&support.elements, &element.name) {
+                file_hits.push((element.offset, format!("<{}>", element.name), reason));
+            }
+            for (offset, attr) in element.attributes {
+                if let Some(reason) = support.attribute_problem(&attr) {
+                    file_hits.push((offset, format!("<{} {attr}>", element.name), reason));
+                }
+            }
+        }
+
+        for (offset, what, reason) in file_hits {
+            if line_text(&text, offset).contains("webf-jsx:ignore") {
+                continue;
+            }
+            let (line, col) = line_col_from_index(&text, offset);
+            let target = if in_ranges(&guarded, offset) { &mut browser_only } else { &mut hits };
+            target.push((rel.clone(), line, col, what, reason));
+        }
+    }
+
+    if guards.mode == BrowserOnlyMode::Warn {
+        let lines: Vec<String> = browser_only
+            .iter()
+            .map(|(file, line, col, what, reason)| format!("- {file}:{line}:{col}  {what}  ({reason})"))
+            .collect();
+        guards.print_warnings("webf-jsx", &lines);
+    }
+
+    if hits.is_empty() {
+        println!("[webf-jsx] OK");
+        return Ok(true);
+    }
+
+    eprintln!("[webf-jsx] Found {} JSX elements or props WebF does not support:", hits.len());
+    for (file, line, col, what, reason) in hits.iter().take(50) {
+        eprintln!("- {file}:{line}:{col}  {what}  ({reason})");
+    }
+    if hits.len() > 50 {
+        eprintln!("...and {} more", hits.len() - 50);
+    }
+    eprintln!("\nThe allowlist lives in docs/jsx_elements.json5. To silence one line, add:");
+    eprintln!("  {{/* webf-jsx:ignore */}}   (or // webf-jsx:ignore)");
+
+    Ok(false)
+}
+
+/// A JSX opening tag: element name and attribute names, with offsets.
+#[derive(Debug)]
+struct JsxElement {
+    offset: usize,
+    name: String,
+    attributes: Vec<(usize, String)>,
+}
+
+/// Opening tags in masked TSX/JSX code. A `<` right after an identifier or a
+/// closing bracket is a type argument or comparison, not a tag.
+fn extract_jsx_elements(code: &str) -> Vec<JsxElement> {
+    let tag_re = Regex::new(r"<([A-Za-z][\w.:-]*)").unwrap();
+    let bytes = code.as_bytes();
+
+    let mut elements = Vec::new();
+    for caps in tag_re.captures_iter(code) {
+        let start = caps.get(0).unwrap().start();
+        let prev = code[..start].chars().next_back();
+        let prev_word = &code[..start].trim_end()[trailing_ident_start(code[..start].trim_end())..];
+        if matches!(prev, Some(c) if c.is_ascii_alphanumeric() || c == '_' || c == '$' || c == ')' || c == ']')
+            && prev_word != "return"
+        {
+            continue;
+        }
+
+        let name = caps.get(1).unwrap();
+        // Attributes run until the `>` that ends the tag; `{...}` values are skipped.
+        let mut attributes = Vec::new();
+        let mut i = name.end();
+        let mut closed = false;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'>' => {
+                    closed = true;
+                    break;
+                }
+                b'{' => i = find_matching_brace(code, i).unwrap_or(bytes.len()) + 1,
+                b'"' | b'\'' => {
+                    let quote = bytes[i];
+                    i = code[i + 1..].find(quote as char).map(|p| i + 1 + p + 1).unwrap_or(bytes.len());
+                }
+                c if c.is_ascii_alphabetic() || c == b'_' => {
+                    let len = code[i..]
+                        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == ':'))
+                        .unwrap_or(code.len() - i);
+                    attributes.push((i, code[i..i + len].to_string()));
+                    i += len;
+                }
+                _ => i += 1,
+            }
+        }
+        // `a<b && c>d` style comparisons never reach a `>` cleanly; skip unterminated tags.
+        if closed {
+            elements.push(JsxElement {
+                offset: start,
+                name: name.as_str().to_string(),
+                attributes,
+            });
+        }
+    }
+
+    elements
+}
+
+/// `docs/canvas_2d.json5`: CanvasRenderingContext2D methods and properties.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CanvasSupportList {
+    methods: BTreeMap<String, ApiSupport>,
+    properties: BTreeMap<String, ApiSupport>,
+}
+
+impl CanvasSupportList {
+    fn load(repo_root: &Path) -> Result<Self> {
+        let path = repo_root.join("docs").join("canvas_2d.json5");
+        let raw = fs::read_to_string(&path).with_context(|| format!("read {}", path.display()))?;
+        json5::from_str(&raw).with_context(|| format!("parse {}", path.display()))
+    }
+}
+
+fn check_canvas_2d(
+    frontend_root: &Path,
+    repo_root: &Path,
+    scan_files: &ScanFiles,
+    guards: &BrowserGuards,
+) -> Result<bool> {
+    let support = CanvasSupportList::load(repo_root)?;
+
+    let mut hits: Vec<(String, usize, usize, String, String)> = Vec::new();
+    // Hits in code excluded by a WebF guard.
+    let mut browser_only: Vec<(String, usize, usize, String, String)> = Vec::new();
+
+    for path in scan_files.with_extensions(&["ts", "tsx", "js", "jsx", "vue"]) {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("read {}", path.display()))?;
+        if !text.contains("getContext") && !text.contains("CanvasRenderingContext2D") {
+            continue;
+        }
+        if text.contains("webf-canvas:disable") {
+            continue;
+        }
+
+        let rel = pathdiff(frontend_root, path);
+        let guarded = guards.ranges(path, &text);
+        for (start, end) in script_ranges(path, &text) {
+            let block = &text[start..end];
+            for (offset, member, is_call) in canvas_member_uses(block) {
+                let offset = start + offset;
+                let (list, kind) = if is_call {
+                    (&support.methods, "call")
+                } else {
+                    (&support.properties, "write")
+                };
+                let Some(reason) = allowlist_problem(list, &member) else {
+                    continue;
+                };
+                if line_text(&text, offset).contains("webf-canvas:ignore") {
+                    continue;
+                }
+                let (line, col) = line_col_from_index(&text, offset);
+                let target = if in_ranges(&guarded, offset) { &mut browser_only } else { &mut hits };
+                target.push((rel.clone(), line, col, format!("{member} ({kind})"), reason));
+            }
+        }
+    }
+
+    if guards.mode == BrowserOnlyMode::Warn {
+        let lines: Vec<String> = browser_only
+            .iter()
+            .map(|(file, line, col, what, reason)| format!("- {file}:{line}:{col}  {what}  ({reason})"))
+            .collect();
+        guards.print_warnings("webf-canvas", &lines);
+    }
+
+    if hits.is_empty() {
+        println!("[webf-canvas] OK");
+        return Ok(true);
+    }
+
+    eprintln!("[webf-canvas] Found {} CanvasRenderingContext2D uses WebF does not support:", hits.len());
+    for (file, line, col, what, reason) in hits.iter().take(50) {
+        eprintln!("- {file}:{line}:{col}  {what}  ({reason})");
+    }
+    if hits.len() > 50 {
+        eprintln!("...and {} more", hits.len() - 50);
+    }
+    eprintln!("\nThe supported subset lives in docs/canvas_2d.json5. To silence one line, add:");
+    eprintln!("  // webf-canvas:ignore");
+
+    Ok(false)
+}
+
+/// Method calls (`true`) and property writes (`false`) on identifiers bound to
+/// a 2D context: `x = canvas.getContext('2d')` or `x: CanvasRenderingContext2D`.
+fn canvas_member_uses(text: &str) -> Vec<(usize, String, bool)> {
+    let get_context_re =
+        Regex::new(r#"\b(?:const|let|var)\s+(\w+)\s*=\s*[^;\n]*?\.getContext\(\s*['"]2d['"]"#).unwrap();
+    let typed_re = Regex::new(r"\b(\w+)\s*\??:\s*CanvasRenderingContext2D\b").unwrap();
+    let assign_re = Regex::new(r#"\b(\w+)\s*=\s*[^;\n=]*?\.getContext\(\s*['"]2d['"]"#).unwrap();
+
+    let code = mask_js_non_code(text);
+    let mut names: BTreeSet<String> = BTreeSet::new();
+    // getContext('2d') needs the quoted argument, so match the raw text; the
+    // mask only drops matches inside comments and strings.
+    for re in [&get_context_re, &assign_re] {
+        for caps in re.captures_iter(text) {
+            if code.as_bytes()[caps.get(0).unwrap().start()] != b' ' {
+                names.insert(caps[1].to_string());
+            }
+        }
+    }
+    names.extend(typed_re.captures_iter(&code).map(|caps| caps[1].to_string()));
+
+    let mut uses = Vec::new();
+    for name in names {
+        let use_re = Regex::new(&format!(
+            r"\b{}\s*!?\s*\??\.\s*([A-Za-z_$][\w$]*)\s*(\(|[-+*/]?=)?",
+            regex::escape(&name)
+        ))
+        .unwrap();
+        for caps in use_re.captures_iter(&code) {
+            let member = caps.get(1).unwrap();
+            // `other.ctx` is a different binding; `this.ctx` is the tracked one.
+            let before = code[..caps.get(0).unwrap().start()].trim_end();
+            if before.ends_with('.') && !before.ends_with("this.") {
+                continue;
+            }
+            let Some(op) = caps.get(2) else {
+                continue;
+            };
+            if op.as_str() == "(" {
+                uses.push((member.start(), member.as_str().to_string(), true));
+            } else if !(op.as_str() == "=" && code[op.end()..].starts_with('=')) {
+                // `==`/`===` comparisons are reads, not writes.
+                uses.push((member.start(), member.as_str().to_string(), false));
+            }
+        }
+    }
+    uses.sort();
+    uses
+}
+
+/// ECMAScript features the dist syntax check detects: id, edition, description.
+const ES_FEATURES: &[(&str, u16, &str)] = &[
+    ("es2015-builtins", 2015, "ES2015 built-ins (`Object.assign`, `Array.from`, `Math.trunc`, ...)"),
+    ("exponent-operator", 2016, "exponent operator `**`"),
+    ("array-includes", 2016, "`.includes()`"),
+    ("object-values-entries", 2017, "`Object.values`/`Object.entries`"),
+    ("string-padding", 2017, "`padStart`/`padEnd`"),
+    ("async-iteration", 2018, "`for await`"),
+    ("regexp-lookbehind", 2018, "RegExp lookbehind `(?<=`/`(?<!`"),
+    ("regexp-named-groups", 2018, "RegExp named groups `(?<name>`"),
+    ("regexp-dotall", 2018, "RegExp `s` flag"),
+    ("regexp-unicode-property", 2018, "RegExp `\\p{...}` escapes"),
+    ("optional-catch-binding", 2019, "`catch {}` without a binding"),
+    ("optional-chaining", 2020, "optional chaining `?.`"),
+    ("nullish-coalescing", 2020, "nullish coalescing `??`"),
+    ("bigint", 2020, "BigInt literal"),
+    ("dynamic-import", 2020, "dynamic `import()`"),
+    ("import-meta", 2020, "`import.meta`"),
+    ("logical-assignment", 2021, "logical assignment `&&=`/`||=`/`??=`"),
+    ("numeric-separators", 2021, "numeric separator `1_000`"),
+    ("string-replace-all", 2021, "`String.prototype.replaceAll`"),
+    ("promise-any", 2021, "`Promise.any`"),
+    ("weak-ref", 2021, "`WeakRef`/`FinalizationRegistry`"),
+    ("class-fields", 2022, "class fields"),
+    ("private-members", 2022, "private class members `#x`"),
+    ("private-in", 2022, "`#x in obj` brand check"),
+    ("class-static-block", 2022, "class `static {}` block"),
+    ("top-level-await", 2022, "top-level `await`"),
+    ("regexp-match-indices", 2022, "RegExp `d` flag"),
+    ("array-at", 2022, "`.at()`"),
+    ("object-has-own", 2022, "`Object.hasOwn`"),
+    ("array-find-last", 2023, "`findLast`/`findLastIndex`"),
+    ("change-array-by-copy", 2023, "`toSorted`/`toReversed`/`toSpliced`"),
+    ("regexp-v-flag", 2024, "RegExp `v` flag"),
+    ("object-group-by", 2024, "`Object.groupBy`/`Map.groupBy`"),
+    ("promise-with-resolvers", 2024, "`Promise.withResolvers`"),
+    ("string-well-formed", 2024, "`isWellFormed`/`toWellFormed`"),
+    ("array-from-async", 2024, "`Array.fromAsync`"),
+    ("set-methods", 2025, "`Set` methods (`union`, `intersection`, ...)"),
+    ("regexp-modifiers", 2025, "RegExp modifiers `(?i:...)`"),
+    ("promise-try", 2025, "`Promise.try`"),
+];
+
+/// Built-in calls by `(object, method)`; `None` matches any receiver.
+const ES_BUILTIN_CALLS: &[(Option<&str>, &str, &str)] = &[
+    (Some("Object"), "assign", "es2015-builtins"),
+    (Some("Array"), "from", "es2015-builtins"),
+    (Some("Array"), "of", "es2015-builtins"),
+    (Some("Math"), "trunc", "es2015-builtins"),
+    (Some("Math"), "sign", "es2015-builtins"),
+    (Some("Math"), "hypot", "es2015-builtins"),
+    (Some("Math"), "log2", "es2015-builtins"),
+    (Some("Math"), "log10", "es2015-builtins"),
+    (Some("Math"), "cbrt", "es2015-builtins"),
+    (Some("Number"), "isInteger", "es2015-builtins"),
+    (Some("Number"), "isFinite", "es2015-builtins"),
+    (Some("Number"), "isNaN", "es2015-builtins"),
+    (None, "startsWith", "es2015-builtins"),
+    (None, "endsWith", "es2015-builtins"),
+    (None, "repeat", "es2015-builtins"),
+    (None, "findIndex", "es2015-builtins"),
+    (None, "includes", "array-includes"),
+    (Some("Object"), "values", "object-values-entries"),
+    (Some("Object"), "entries", "object-values-entries"),
+    (None, "padStart", "string-padding"),
+    (None, "padEnd", "string-padding"),
+    (None, "replaceAll", "string-replace-all"),
+    (None, "at", "array-at"),
+    (None, "findLast", "array-find-last"),
+    (None, "findLastIndex", "array-find-last"),
+    (None, "toSorted", "change-array-by-copy"),
+    (None, "toReversed", "change-array-by-copy"),
+    (None, "toSpliced", "change-array-by-copy"),
+    (None, "isWellFormed", "string-well-formed"),
+    (None, "toWellFormed", "string-well-formed"),
+    (None, "symmetricDifference", "set-methods"),
+    (None, "isSubsetOf", "set-methods"),
+    (None, "isSupersetOf", "set-methods"),
+    (None, "isDisjointFrom", "set-methods"),
+    (Some("Object"), "hasOwn", "object-has-own"),
+    (Some("Object"), "groupBy", "object-group-by"),
+    (Some("Map"), "groupBy", "object-group-by"),
+    (Some("Promise"), "any", "promise-any"),
+    (Some("Promise"), "withResolvers", "promise-with-resolvers"),
+    (Some("Promise"), "try", "promise-try"),
+    (Some("Array"), "fromAsync", "array-from-async"),
+];
+
+/// Methods strings (or user types) share with arrays: only flagged when the
+/// receiver is known to be an array, since `'abc'.includes(x)` is ES2015.
+const ARRAY_ONLY_BUILTINS: &[&str] = &["includes", "at"];
+
+/// Calls known to return an array (`xs.map(...)`, `Object.keys(o)`, `s.split(',')`).
+const ARRAY_RETURNING_METHODS: &[&str] = &["map", "filter", "flat", "flatMap", "split", "sort", "reverse"];
+
+static NAMED_GROUP_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\(\?<[A-Za-z_$]").unwrap());
+static REGEXP_MODIFIERS_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\(\?[ims]*-?[ims]+:").unwrap());
+
+/// Whether an expression evaluates to an array without knowing any types.
+fn is_array_expression(expr: &Expression) -> bool {
+    match expr.without_parentheses() {
+        Expression::ArrayExpression(_) => true,
+        Expression::NewExpression(new) => matches!(&new.callee, Expression::Identifier(id) if id.name == "Array"),
+        Expression::CallExpression(call) => {
+            let Expression::StaticMemberExpression(member) = call.callee.without_parentheses() else {
+                return false;
+            };
+            let method = member.property.name.as_str();
+            match &member.object {
+                Expression::Identifier(id) if id.name == "Array" => matches!(method, "from" | "of"),
+                Expression::Identifier(id) if id.name == "Object" => matches!(method, "keys" | "values" | "entries"),
+                object => ARRAY_RETURNING_METHODS.contains(&method) && (method == "split" || is_array_expression(object)),
+            }
+        }
+        _ => false,
+    }
+}
+
+/// `docs/js_engine.json5`: engine profiles for the dist syntax check.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsEngineProfiles {
+    default: String,
+    profiles: BTreeMap<String, JsEngineProfile>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsEngineProfile {
+    /// Newest fully supported ECMAScript edition.
+    es: u16,
+    #[serde(default)]
+    allow: Vec<String>,
+    #[serde(default)]
+    deny: Vec<String>,
+}
+
+impl JsEngineProfile {
+    fn rejects(&self, feature: &str, edition: u16) -> bool {
+        self.deny.iter().any(|f| f == feature) || (edition > self.es && !self.allow.iter().any(|f| f == feature))
+    }
+}
+
+/// Load the named profile (or the default), rejecting unknown feature ids.
+fn load_js_engine_profile(repo_root: &Path, name: Option<&str>) -> Result<(String, JsEngineProfile)> {
+    let path = repo_root.join("docs").join("js_engine.json5");
+    let raw = fs::read_to_string(&path).with_context(|| format!("read {}", path.display()))?;
+    let mut config: JsEngineProfiles =
+        json5::from_str(&raw).with_context(|| format!("parse {}", path.display()))?;
+
+    let name = name.map(str::to_string).unwrap_or(config.default);
+    let profile = config.profiles.remove(&name).ok_or_else(|| {
+        anyhow!("unknown JS engine profile `{name}` (see {})", path.display())
+    })?;
+    for feature in profile.allow.iter().chain(profile.deny.iter()) {
+        if !ES_FEATURES.iter().any(|(id, _, _)| id == feature) {
+            return Err(anyhow!("profile `{name}` in {}: unknown feature `{feature}`", path.display()));
+        }
+    }
+    Ok((name, profile))
+}
+
+fn check_dist_es_syntax(
+    frontend_root: &Path,
+    dist_dir: &Path,
+    repo_root: &Path,
+    engine: Option<&str>,
+) -> Result<bool> {
+    let (engine_name, profile) = load_js_engine_profile(repo_root, engine)?;
+
+    let js_files = collect_dist_files(dist_dir, &["js", "mjs"]);
+    if js_files.is_empty() {
+        println!(
+            "[webf-es-syntax] skipped: no JS chunks in {} (build first)",
+            pathdiff(frontend_root, dist_dir)
+        );
+        return Ok(true);
+    }
+
+    // (chunk, feature, origin) -> (first offset, first line:col, count)
+    let mut grouped: BTreeMap<(String, &'static str, String), (usize, String, usize)> = BTreeMap::new();
+    let mut parse_errors: Vec<(String, String)> = Vec::new();
+
+    for js_file in js_files.iter() {
+        let text = fs::read_to_string(js_file)
+            .with_context(|| format!("read {}", js_file.display()))?;
+        let chunk = pathdiff(frontend_root, js_file);
+
+        let found = match detect_es_features(&text, SourceType::mjs()) {
+            Ok(found) => found,
+            Err(message) => {
+                parse_errors.push((chunk, message));
+                continue;
+            }
+        };
+
+        let source_map = SourceMap::load_for(js_file, &text);
+        for (offset, feature) in found {
+            let Some(&(_, edition, _)) = ES_FEATURES.iter().find(|(id, _, _)| *id == feature) else {
+                continue;
+            };
+            if !profile.rejects(feature, edition) {
+                continue;
+            }
+            let (line0, col0) = line_col0(&text, offset);
+            let origin = source_map
+                .as_ref()
+                .and_then(|map| map.lookup(line0, col0))
+                .map(|(source, line, col)| {
+                    let source = describe_source_map_origin(js_file, source, frontend_root);
+                    format!("{source}:{}:{}", line + 1, col + 1)
+                })
+                .unwrap_or_default();
+            let origin_file = origin.rsplitn(3, ':').last().unwrap_or("").to_string();
+            let entry = grouped
+                .entry((chunk.clone(), feature, origin_file))
+                .or_insert((offset, origin, 0));
+            entry.2 += 1;
+        }
+    }
+
+    if grouped.is_empty() && parse_errors.is_empty() {
+        println!("[webf-es-syntax] OK (engine profile `{engine_name}`, ES{})", profile.es);
+        return Ok(true);
+    }
+
+    if !grouped.is_empty() {
+        eprintln!(
+            "[webf-es-syntax] Found {} features above the `{engine_name}` engine profile (ES{}):",
+            grouped.len(),
+            profile.es
+        );
+        for ((chunk, feature, _), (offset, origin, count)) in grouped.iter().take(50) {
+            let (_, edition, description) = ES_FEATURES.iter().find(|(id, _, _)| id == feature).unwrap();
+            let times = if *count > 1 { format!(" x{count}") } else { String::new() };
+            let origin = if origin.is_empty() { String::new() } else { format!("  <- {origin}") };
+            eprintln!("- {chunk}@{offset}  {description} [{feature}, ES{edition}]{times}{origin}");
+        }
+        if grouped.len() > 50 {
+            eprintln!("...and {} more", grouped.len() - 50);
+        }
+    }
+    for (chunk, message) in parse_errors.iter() {
+        eprintln!("[webf-es-syntax] {chunk}: could not parse chunk: {message}");
+    }
+    eprintln!("\nLower the build target (vite `build.target`) or add a polyfill, or pick another");
+    eprintln!("profile from docs/js_engine.json5 with --js-engine.");
+
+    Ok(false)
+}
+
+/// Source-map `source` relative to the project root, or `pkg: path` for npm files.
+fn describe_source_map_origin(generated: &Path, source: &str, frontend_root: &Path) -> String {
+    let mut path = generated.parent().map(Path::to_path_buf).unwrap_or_default();
+    for part in source.trim_start_matches("webpack://").split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                path.pop();
+            }
+            part => path.push(part),
+        }
+    }
+    let rel = pathdiff(frontend_root, &path);
+    match npm_package_of(&rel) {
+        Some(package) => format!("{package}: {rel}"),
+        None => rel,
+    }
+}
+
+/// Byte offsets and ids of the ES features used in a JS chunk.
+fn detect_es_features(text: &str, source_type: SourceType) -> std::result::Result<Vec<(usize, &'static str)>, String> {
+    let allocator = Allocator::default();
+    let ret = JsParser::new(&allocator, text, source_type).parse();
+    if ret.panicked {
+        let message = ret.errors.first().map(|e| e.to_string()).unwrap_or_default();
+        return Err(message);
+    }
+
+    let mut visitor = EsFeatureVisitor::default();
+    visitor.visit_program(&ret.program);
+    Ok(visitor.found)
+}
+
+#[derive(Default)]
+struct EsFeatureVisitor {
+    found: Vec<(usize, &'static str)>,
+    function_depth: u32,
+}
+
+impl EsFeatureVisitor {
+    fn push(&mut self, span: oxc_span::Span, feature: &'static str) {
+        self.found.push((span.start as usize, feature));
+    }
+}
+
+impl<'a> Visit<'a> for EsFeatureVisitor {
+    fn visit_function(&mut self, it: &Function<'a>, flags: ScopeFlags) {
+        if it.r#async && it.generator {
+            self.push(it.span, "async-iteration");
+        }
+        walk::walk_function(self, it, flags);
+    }
+
+    fn visit_function_body(&mut self, it: &FunctionBody<'a>) {
+        self.function_depth += 1;
+        walk::walk_function_body(self, it);
+        self.function_depth -= 1;
+    }
+
+    fn visit_await_expression(&mut self, it: &AwaitExpression<'a>) {
+        if self.function_depth == 0 {
+            self.push(it.span, "top-level-await");
+        }
+        walk::walk_await_expression(self, it);
+    }
+
+    fn visit_for_of_statement(&mut self, it: &ForOfStatement<'a>) {
+        if it.r#await {
+            self.push(it.span, "async-iteration");
+            if self.function_depth == 0 {
+                self.push(it.span, "top-level-await");
+            }
+        }
+        walk::walk_for_of_statement(self, it);
+    }
+
+    fn visit_catch_clause(&mut self, it: &CatchClause<'a>) {
+        if it.param.is_none() {
+            self.push(it.span, "optional-catch-binding");
+        }
+        walk::walk_catch_clause(self, it);
+    }
+
+    fn visit_chain_expression(&mut self, it: &ChainExpression<'a>) {
+        self.push(it.span, "optional-chaining");
+        walk::walk_chain_expression(self, it);
+    }
+
+    fn visit_binary_expression(&mut self, it: &BinaryExpression<'a>) {
+        if it.operator == BinaryOperator::Exponential {
+            self.push(it.span, "exponent-operator");
+        }
+        walk::walk_binary_expression(self, it);
+    }
+
+    fn visit_logical_expression(&mut self, it: &LogicalExpression<'a>) {
+        if it.operator == LogicalOperator::Coalesce {
+            self.push(it.span, "nullish-coalescing");
+        }
+        walk::walk_logical_expression(self, it);
+    }
+
+    fn visit_assignment_expression(&mut self, it: &AssignmentExpression<'a>) {
+        if it.operator.is_logical() {
+            self.push(it.span, "logical-assignment");
+        }
+        if it.operator == AssignmentOperator::Exponential {
+            self.push(it.span, "exponent-operator");
+        }
+        walk::walk_assignment_expression(self, it);
+    }
+
+    fn visit_big_int_literal(&mut self, it: &BigIntLiteral<'a>) {
+        self.push(it.span, "bigint");
+    }
+
+    fn visit_numeric_literal(&mut self, it: &NumericLiteral<'a>) {
+        if it.raw.is_some_and(|raw| raw.contains('_')) {
+            self.push(it.span, "numeric-separators");
+        }
+    }
+
+    fn visit_import_expression(&mut self, it: &ImportExpression<'a>) {
+        self.push(it.span, "dynamic-import");
+        walk::walk_import_expression(self, it);
+    }
+
+    fn visit_meta_property(&mut self, it: &MetaProperty<'a>) {
+        if it.meta.name == "import" {
+            self.push(it.span, "import-meta");
+        }
+    }
+
+    fn visit_property_definition(&mut self, it: &PropertyDefinition<'a>) {
+        self.push(it.span, "class-fields");
+        walk::walk_property_definition(self, it);
+    }
+
+    fn visit_private_identifier(&mut self, it: &PrivateIdentifier<'a>) {
+        self.push(it.span, "private-members");
+    }
+
+    fn visit_private_in_expression(&mut self, it: &PrivateInExpression<'a>) {
+        self.push(it.span, "private-in");
+        walk::walk_private_in_expression(self, it);
+    }
+
+    fn visit_static_block(&mut self, it: &StaticBlock<'a>) {
+        self.push(it.span, "class-static-block");
+        walk::walk_static_block(self, it);
+    }
+
+    fn visit_identifier_reference(&mut self, it: &IdentifierReference<'a>) {
+        if it.name == "WeakRef" || it.name == "FinalizationRegistry" {
+            self.push(it.span, "weak-ref");
+        }
+    }
+
+    fn visit_call_expression(&mut self, it: &CallExpression<'a>) {
+        if let Expression::StaticMemberExpression(member) = it.callee.without_parentheses() {
+            let object = match &member.object {
+                Expression::Identifier(ident) => Some(ident.name.as_str()),
+                _ => None,
+            };
+            let method = member.property.name.as_str();
+            let builtin = ES_BUILTIN_CALLS
+                .iter()
+                .find(|(obj, name, _)| *name == method && (obj.is_none() || *obj == object));
+            let known_receiver = !ARRAY_ONLY_BUILTINS.contains(&method) || is_array_expression(&member.object);
+            if let Some(&(_, _, feature)) = builtin.filter(|_| known_receiver) {
+                self.push(member.property.span, feature);
+            }
+        }
+        walk::walk_call_expression(self, it);
+    }
+
+    fn visit_reg_exp_literal(&mut self, it: &RegExpLiteral<'a>) {
+        let flags = it.regex.flags;
+        let pattern = it.regex.pattern.text.as_str();
+        if flags.contains(RegExpFlags::V) {
+            self.push(it.span, "regexp-v-flag");
+        }
+        if flags.contains(RegExpFlags::D) {
+            self.push(it.span, "regexp-match-indices");
+        }
+        if flags.contains(RegExpFlags::S) {
+            self.push(it.span, "regexp-dotall");
+        }
+        if pattern.contains("(?<=") || pattern.contains("(?<!") {
+            self.push(it.span, "regexp-lookbehind");
+        }
+        if NAMED_GROUP_RE.is_match(pattern) {
+            self.push(it.span, "regexp-named-groups");
+        }
+        if pattern.contains("\\p{") || pattern.contains("\\P{") {
+            self.push(it.span, "regexp-unicode-property");
+        }
+        if REGEXP_MODIFIERS_RE.is_match(pattern) {
+            self.push(it.span, "regexp-modifiers");
+        }
+    }
+}
+
+#[cfg(test)]
+mod es_feature_tests {
+    use super::detect_es_features;
+    use oxc_span::SourceType;
+
+    fn features(src: &str) -> Vec<&'static str> {
+        detect_es_features(src, SourceType::mjs()).unwrap().into_iter().map(|(_, f)| f).collect()
+    }
+
+    #[test]
+    fn skips_includes_and_at_on_unknown_receivers() {
+        assert!(features("const ok = name.includes('x'); 'abc'.includes('b'); list.at(-1);").is_empty());
+    }
+
+    #[test]
+    fn flags_includes_and_at_on_arrays() {
+        let src = "[1, 2].includes(x); Object.keys(o).at(-1); s.split(',').includes('a'); xs.map(f).includes(1);";
+        assert_eq!(features(src), ["array-includes", "array-at", "array-includes"]);
+        assert_eq!(features("Array.from(set).filter(f).at(0);"), ["array-at", "es2015-builtins"]);
+    }
+
+    #[test]
+    fn flags_async_generators() {
+        assert_eq!(features("async function* gen() {}"), ["async-iteration"]);
+        assert_eq!(features("class A { async *items() {} }"), ["async-iteration"]);
+        assert!(features("async function f() {} function* g() {}").is_empty());
+    }
+
+    #[test]
+    fn flags_regexp_syntax() {
+        assert_eq!(features("/(?<year>\\d{4})/;"), ["regexp-named-groups"]);
+        assert_eq!(features("/(?i:a)b/;"), ["regexp-modifiers"]);
+    }
+}
+
+/// Effect catalog, relative to the project root.
+const EFFECTS_DIR: &str = "public/effects";
+
+/// Files every effect package needs.
+const EFFECT_PACKAGE_FILES: &[&str] = &["effect.ts", "meta.json", "ui.json"];
+
+/// `public/effects/manifest.json`.
+#[derive(Debug, Deserialize)]
+struct EffectManifest {
+    effects: Vec<String>,
+}
+
+/// `public/effects/<id>/meta.json`.
+#[derive(Debug, Deserialize)]
+struct EffectMeta {
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+/// Validates the effect catalog: manifest vs directories, package files, meta.json
+/// and each ui.json (element tree, `$bindState` pointers, bridge keys, speed range,
+/// component types and props against the json-render catalog, state keys against
+/// the effect's `setConfig` handler and config interface).
+fn check_effects_catalog(frontend_root: &Path) -> Result<bool> {
+    let effects_dir = frontend_root.join(EFFECTS_DIR);
+    let manifest_path = effects_dir.join("manifest.json");
+    if !manifest_path.is_file() {
+        println!("[webf-effects] skipped: no {EFFECTS_DIR}/manifest.json");
+        return Ok(true);
+    }
+
+    // (file, item, detail)
+    let mut hits: Vec<(String, String, String)> = Vec::new();
+    let rel = |p: &Path| pathdiff(frontend_root, p);
+
+    let raw = fs::read_to_string(&manifest_path)
+        .with_context(|| format!("read {}", manifest_path.display()))?;
+    let manifest: EffectManifest = match serde_json::from_str(&raw) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            eprintln!("[webf-effects] {}: invalid manifest: {e}", rel(&manifest_path));
+            return Ok(false);
+        }
+    };
+
+    let catalog = EffectUiCatalog::load(frontend_root, &mut hits);
+
+    let mut listed: BTreeSet<&str> = BTreeSet::new();
+    for id in manifest.effects.iter() {
+        if !listed.insert(id) {
+            hits.push((rel(&manifest_path), id.clone(), "listed more than once".to_string()));
+        }
+    }
+
+    for id in listed.iter() {
+        let dir = effects_dir.join(id);
+        if !dir.is_dir() {
+            hits.push((rel(&manifest_path), id.to_string(), format!("missing directory {EFFECTS_DIR}/{id}/")));
+            continue;
+        }
+        for file in EFFECT_PACKAGE_FILES {
+            if !dir.join(file).is_file() {
+                hits.push((rel(&dir), id.to_string(), format!("missing {file}")));
+            }
+        }
+
+        let ui_path = dir.join("ui.json");
+        if let Ok(raw) = fs::read_to_string(&ui_path) {
+            match serde_json::from_str::<serde_json::Value>(&raw) {
+                Ok(ui) => {
+                    for (at, detail) in validate_effect_ui(&ui, catalog.as_ref()) {
+                        hits.push((rel(&ui_path), at, detail));
+                    }
+                    let effect_path = dir.join("effect.ts");
+                    if let Ok(source) = fs::read_to_string(&effect_path) {
+                        match extract_effect_config_keys(&source) {
+                            Ok(keys) => {
+                                let sent = ui_config_keys(&ui);
+                                for (state_key, config_key) in sent.iter() {
+                                    if !keys.handled.contains(config_key)
+                                        && !EFFECT_BASE_CONFIG_KEYS.contains(&config_key.as_str())
+                                    {
+                                        hits.push((
+                                            rel(&ui_path),
+                                            format!("state.{EFFECT_UI_STATE_SCOPE}.{state_key}"),
+                                            format!("sent as setConfig('{config_key}'), which effect.ts ignores"),
+                                        ));
+                                    }
+                                }
+                                for field in keys.fields.iter().filter(|f| !sent.iter().any(|(_, k)| k == *f)) {
+                                    hits.push((
+                                        rel(&effect_path),
+                                        field.clone(),
+                                        "config field is never set from ui.json".to_string(),
+                                    ));
+                                }
+                            }
+                            Err(e) => hits.push((rel(&effect_path), id.to_string(), format!("parse error: {e}"))),
+                        }
+                    }
+                }
+                Err(e) => hits.push((rel(&ui_path), id.to_string(), format!("invalid JSON: {e}"))),
+            }
+        }
+
+        let meta_path = dir.join("meta.json");
+        let Ok(raw) = fs::read_to_string(&meta_path) else {
+            continue;
+        };
+        let meta: EffectMeta = match serde_json::from_str(&raw) {
+            Ok(meta) => meta,
+            Err(e) => {
+                hits.push((rel(&meta_path), id.to_string(), format!("invalid JSON: {e}")));
+                continue;
+            }
+        };
+        match meta.id.as_deref() {
+            Some(meta_id) if meta_id == *id => {}
+            Some(meta_id) => hits.push((
+                rel(&meta_path),
+                "id".to_string(),
+                format!("`{meta_id}` does not match directory `{id}`"),
+            )),
+            None => hits.push((rel(&meta_path), "id".to_string(), "missing".to_string())),
+        }
+        for (field, value) in [("name", &meta.name), ("description", &meta.description)] {
+            if value.as_deref().is_none_or(|v| v.trim().is_empty()) {
+                hits.push((rel(&meta_path), field.to_string(), "missing or empty".to_string()));
+            }
+        }
+    }
+
+    // Orphans: effect-like directories the manifest does not list.
+    let entries = fs::read_dir(&effects_dir)
+        .with_context(|| format!("read {}", effects_dir.display()))?;
+    let mut orphans: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_dir() && EFFECT_PACKAGE_FILES.iter().any(|f| p.join(f).is_file()))
+        .filter(|p| !listed.contains(p.file_name().and_then(|n| n.to_str()).unwrap_or("")))
+        .collect();
+    orphans.sort();
+    for dir in orphans {
+        let id = dir.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        hits.push((rel(&dir), id, "not listed in manifest.json".to_string()));
+    }
+
+    if hits.is_empty() {
+        println!("[webf-effects] OK ({} effects)", listed.len());
+        return Ok(true);
+    }
+
+    eprintln!("[webf-effects] Found {} problems in the effect catalog:", hits.len());
+    for (file, item, detail) in hits.iter().take(50) {
+        eprintln!("- {file}  {item}  ({detail})");
+    }
+    if hits.len() > 50 {
+        eprintln!("...and {} more", hits.len() - 50);
+    }
+
+    Ok(false)
+}
+
+/// Top-level keys of an effect `ui.json`.
+const EFFECT_UI_KEYS: &[&str] = &["root", "elements", "state", "speed", "bridge"];
+
+/// State object that `bridge` keys refer to (`/effect/<key>`).
+const EFFECT_UI_STATE_SCOPE: &str = "effect";
+
+/// Structural problems in an effect `ui.json`: `(location, detail)`.
+fn validate_effect_ui(ui: &serde_json::Value, catalog: Option<&EffectUiCatalog>) -> Vec<(String, String)> {
+    use serde_json::Value;
+
+    let mut out: Vec<(String, String)> = Vec::new();
+    let Some(ui) = ui.as_object() else {
+        return vec![("(root)".to_string(), "expected an object".to_string())];
+    };
+    for key in ui.keys() {
+        if !EFFECT_UI_KEYS.contains(&key.as_str()) {
+            out.push((key.clone(), "unknown key".to_string()));
+        }
+    }
+
+    let empty = serde_json::Map::new();
+    let elements = match ui.get("elements") {
+        Some(Value::Object(elements)) => elements,
+        Some(_) => {
+            out.push(("elements".to_string(), "expected an object".to_string()));
+            &empty
+        }
+        None => {
+            out.push(("elements".to_string(), "missing".to_string()));
+            &empty
+        }
+    };
+    let state = ui.get("state").cloned().unwrap_or(Value::Object(Default::default()));
+    if !state.is_object() {
+        out.push(("state".to_string(), "expected an object".to_string()));
+    }
+
+    // Element shape and child references.
+    let mut children_of: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (id, element) in elements.iter() {
+        let at = format!("elements.{id}");
+        let Some(element) = element.as_object() else {
+            out.push((at, "expected an object".to_string()));
+            continue;
+        };
+        if !element.get("type").is_some_and(Value::is_string) {
+            out.push((format!("{at}.type"), "missing or not a string".to_string()));
+        }
+        if element.get("props").is_some_and(|p| !p.is_object()) {
+            out.push((format!("{at}.props"), "expected an object".to_string()));
+        }
+        if let Some(catalog) = catalog {
+            out.extend(catalog.element_problems(&at, element));
+        }
+        let children = children_of.entry(id.as_str()).or_default();
+        match element.get("children") {
+            None => {}
+            Some(Value::Array(items)) => {
+                for (i, child) in items.iter().enumerate() {
+                    match child.as_str() {
+                        Some(child) if elements.contains_key(child) => children.push(child),
+                        Some(child) => out.push((format!("{at}.children[{i}]"), format!("unknown element `{child}`"))),
+                        None => out.push((format!("{at}.children[{i}]"), "expected an element id".to_string())),
+                    }
+                }
+            }
+            Some(_) => out.push((format!("{at}.children"), "expected an array".to_string())),
+        }
+        if let Some(props) = element.get("props") {
+            for (pointer_at, pointer) in collect_bind_state_pointers(props, &format!("{at}.props")) {
+                if resolve_json_pointer(&state, &pointer).is_none() {
+                    out.push((pointer_at, format!("$bindState `{pointer}` does not resolve into `state`")));
+                }
+            }
+        }
+    }
+
+    // Root, cycles and reachability.
+    match ui.get("root").and_then(Value::as_str) {
+        Some(root) if elements.contains_key(root) => {
+            let mut reachable = BTreeSet::new();
+            let mut path = Vec::new();
+            visit_ui_element(root, &children_of, &mut reachable, &mut path, &mut out);
+            for id in elements.keys() {
+                if !reachable.contains(id.as_str()) {
+                    out.push((format!("elements.{id}"), format!("not reachable from root `{root}`")));
+                }
+            }
+        }
+        Some(root) => out.push(("root".to_string(), format!("unknown element `{root}`"))),
+        None => out.push(("root".to_string(), "missing or not a string".to_string())),
+    }
+
+    // Bridge keys name state keys under `/effect`.
+    let scope = state.get(EFFECT_UI_STATE_SCOPE);
+    let has_state_key = |key: &str| scope.and_then(|s| s.get(key)).is_some();
+    if let Some(bridge) = ui.get("bridge") {
+        match bridge.get("colorKeys") {
+            None => {}
+            Some(Value::Array(keys)) => {
+                for (i, key) in keys.iter().enumerate() {
+                    match key.as_str() {
+                        Some(key) if has_state_key(key) => {}
+                        Some(key) => out.push((
+                            format!("bridge.colorKeys[{i}]"),
+                            format!("`{key}` is not a key of state.{EFFECT_UI_STATE_SCOPE}"),
+                        )),
+                        None => out.push((format!("bridge.colorKeys[{i}]"), "expected a string".to_string())),
+                    }
+                }
+            }
+            Some(_) => out.push(("bridge.colorKeys".to_string(), "expected an array".to_string())),
+        }
+        match bridge.get("scaleKeys") {
+            None => {}
+            Some(Value::Object(keys)) => {
+                for (key, factor) in keys.iter() {
+                    if !has_state_key(key) {
+                        out.push((
+                            format!("bridge.scaleKeys.{key}"),
+                            format!("`{key}` is not a key of state.{EFFECT_UI_STATE_SCOPE}"),
+                        ));
+                    }
+                    if !factor.is_number() {
+                        out.push((format!("bridge.scaleKeys.{key}"), "expected a number".to_string()));
+                    }
+                }
+            }
+            Some(_) => out.push(("bridge.scaleKeys".to_string(), "expected an object".to_string())),
+        }
+    }
+
+    // Speed range.
+    if let Some(speed) = ui.get("speed") {
+        let field = |name: &str| speed.get(name).and_then(Value::as_f64);
+        match (field("min"), field("default"), field("max")) {
+            (Some(min), Some(default), Some(max)) => {
+                if !(min <= default && default <= max) {
+                    out.push(("speed".to_string(), format!("expected min <= default <= max, got {min} / {default} / {max}")));
+                }
+            }
+            _ => out.push(("speed".to_string(), "expected numeric `min`, `default` and `max`".to_string())),
+        }
+    }
+
+    out
+}
+
+/// Depth-first walk from `id`, reporting cycles via `path`.
+fn visit_ui_element<'a>(
+    id: &'a str,
+    children_of: &BTreeMap<&'a str, Vec<&'a str>>,
+    reachable: &mut BTreeSet<&'a str>,
+    path: &mut Vec<&'a str>,
+    out: &mut Vec<(String, String)>,
+) {
+    if let Some(pos) = path.iter().position(|p| *p == id) {
+        let cycle: Vec<&str> = path[pos..].iter().copied().chain([id]).collect();
+        out.push((format!("elements.{id}"), format!("cycle: {}", cycle.join(" -> "))));
+        return;
+    }
+    if !reachable.insert(id) {
+        return;
+    }
+    path.push(id);
+    for child in children_of.get(id).into_iter().flatten() {
+        visit_ui_element(child, children_of, reachable, path, out);
+    }
+    path.pop();
+}
+
+/// `$bindState` pointers under `value`, with their location.
+fn collect_bind_state_pointers(value: &serde_json::Value, at: &str) -> Vec<(String, String)> {
+    let mut out = Vec::new();
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, item) in map.iter() {
+                match (key.as_str(), item.as_str()) {
+                    ("$bindState", Some(pointer)) => out.push((at.to_string(), pointer.to_string())),
+                    _ => out.extend(collect_bind_state_pointers(item, &format!("{at}.{key}"))),
+                }
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                out.extend(collect_bind_state_pointers(item, &format!("{at}[{i}]")));
+            }
+        }
+        _ => {}
+    }
+    out
+}
+
+/// RFC 6901 lookup (`/effect/hue`); `None` if the pointer does not resolve.
+fn resolve_json_pointer<'a>(root: &'a serde_json::Value, pointer: &str) -> Option<&'a serde_json::Value> {
+    if pointer.is_empty() {
+        return Some(root);
+    }
+    let mut current = root;
+    for token in pointer.strip_prefix('/')?.split('/') {
+        let token = token.replace("~1", "/").replace("~0", "~");
+        current = match current {
+            serde_json::Value::Object(map) => map.get(&token)?,
+            serde_json::Value::Array(items) => items.get(token.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(current)
+}
+
+/// json-render catalog the effect ui.json component types and props come from.
+const EFFECT_UI_CATALOG: &str = "src/effects/catalog.ts";
+
+/// Renderers for the catalog components (`defineRegistry`).
+const EFFECT_UI_REGISTRY: &str = "src/effects/registry.tsx";
+
+/// Value type of a zod prop schema.
+#[derive(Debug, Clone, PartialEq)]
+enum ZodKind {
+    String,
+    Number,
+    Boolean,
+    Enum(Vec<String>),
+    /// Anything the checker does not type-check (`z.any()`, `z.array(..)`, ...).
+    Other,
+}
+
+#[derive(Debug, Clone)]
+struct ZodProp {
+    kind: ZodKind,
+    optional: bool,
+    nullable: bool,
+}
+
+/// A catalog component: its props schema and whether it declares slots.
+#[derive(Debug, Default)]
+struct UiComponent {
+    props: BTreeMap<String, ZodProp>,
+    slots: bool,
+}
+
+#[derive(Debug, Default)]
+struct EffectUiCatalog {
+    components: BTreeMap<String, UiComponent>,
+}
+
+impl EffectUiCatalog {
+    /// Reads `defineCatalog` from catalog.ts and cross-checks it with `defineRegistry`.
+    /// `None` when the project has no catalog; problems go to `hits`.
+    fn load(frontend_root: &Path, hits: &mut Vec<(String, String, String)>) -> Option<Self> {
+        let catalog_path = frontend_root.join(EFFECT_UI_CATALOG);
+        let text = fs::read_to_string(&catalog_path).ok()?;
+        let components = match define_call_components(&text, SourceType::ts(), "defineCatalog") {
+            Ok(Some(components)) => components,
+            Ok(None) => {
+                hits.push((EFFECT_UI_CATALOG.to_string(), "defineCatalog".to_string(), "no `components` found".to_string()));
+                return None;
+            }
+            Err(e) => {
+                hits.push((EFFECT_UI_CATALOG.to_string(), "defineCatalog".to_string(), format!("parse error: {e}")));
+                return None;
+            }
+        };
+
+        if let Ok(text) = fs::read_to_string(frontend_root.join(EFFECT_UI_REGISTRY)) {
+            match define_call_components(&text, SourceType::tsx(), "defineRegistry") {
+                Ok(Some(renderers)) => {
+                    for name in components.keys().filter(|n| !renderers.contains_key(*n)) {
+                        hits.push((EFFECT_UI_REGISTRY.to_string(), name.clone(), "catalog component has no renderer".to_string()));
+                    }
+                    for name in renderers.keys().filter(|n| !components.contains_key(*n)) {
+                        hits.push((EFFECT_UI_REGISTRY.to_string(), name.clone(), "renderer is not in the catalog".to_string()));
+                    }
+                }
+                Ok(None) => hits.push((EFFECT_UI_REGISTRY.to_string(), "defineRegistry".to_string(), "no `components` found".to_string())),
+                Err(e) => hits.push((EFFECT_UI_REGISTRY.to_string(), "defineRegistry".to_string(), format!("parse error: {e}"))),
+            }
+        }
+
+        Some(Self { components })
+    }
+
+    /// Problems of one ui.json element against its catalog component.
+    fn element_problems(&self, at: &str, element: &serde_json::Map<String, serde_json::Value>) -> Vec<(String, String)> {
+        let mut out = Vec::new();
+        let Some(ty) = element.get("type").and_then(serde_json::Value::as_str) else {
+            return out;
+        };
+        let Some(component) = self.components.get(ty) else {
+            out.push((format!("{at}.type"), format!("unknown component `{ty}` (not in {EFFECT_UI_CATALOG})")));
+            return out;
+        };
+
+        let empty = serde_json::Map::new();
+        let props = element.get("props").and_then(serde_json::Value::as_object).unwrap_or(&empty);
+        for (name, value) in props.iter() {
+            let prop_at = format!("{at}.props.{name}");
+            let Some(schema) = component.props.get(name) else {
+                out.push((prop_at, format!("unknown prop for `{ty}`")));
+                continue;
+            };
+            if let Some(problem) = zod_value_problem(schema, value) {
+                out.push((prop_at, problem));
+            }
+        }
+        for (name, schema) in component.props.iter() {
+            if !schema.optional && !props.contains_key(name) {
+                out.push((format!("{at}.props"), format!("missing required prop `{name}` for `{ty}`")));
+            }
+        }
+
+        if let (Some(min), Some(max)) = (
+            props.get("min").and_then(serde_json::Value::as_f64),
+            props.get("max").and_then(serde_json::Value::as_f64),
+        ) {
+            if min > max {
+                out.push((format!("{at}.props"), format!("min {min} is greater than max {max}")));
+            }
+        }
+        if let Some(color) = props.get("accentColor").and_then(serde_json::Value::as_str) {
+            if !is_css_color_literal(color) {
+                out.push((
+                    format!("{at}.props.accentColor"),
+                    format!("`{color}` is not a color (use #rgb, #rrggbb, rgb() or hsl())"),
+                ));
+            }
+        }
+
+        if !component.slots && element.get("children").and_then(serde_json::Value::as_array).is_some_and(|c| !c.is_empty()) {
+            out.push((format!("{at}.children"), format!("`{ty}` has no slots")));
+        }
+        out
+    }
+}
+
+/// Type problem of a literal prop value; bound values (`{ "$bindState": .. }`) are not checked.
+fn zod_value_problem(schema: &ZodProp, value: &serde_json::Value) -> Option<String> {
+    use serde_json::Value;
+
+    if value.as_object().is_some_and(|o| o.keys().any(|k| k.starts_with('$'))) {
+        return None;
+    }
+    let expected = match (&schema.kind, value) {
+        (_, Value::Null) if schema.nullable => return None,
+        (ZodKind::Other, _) => return None,
+        (ZodKind::String, Value::String(_)) | (ZodKind::Number, Value::Number(_)) | (ZodKind::Boolean, Value::Bool(_)) => {
+            return None;
+        }
+        (ZodKind::Enum(options), Value::String(s)) if options.contains(s) => return None,
+        (ZodKind::String, _) => "a string".to_string(),
+        (ZodKind::Number, _) => "a number".to_string(),
+        (ZodKind::Boolean, _) => "a boolean".to_string(),
+        (ZodKind::Enum(options), _) => format!("one of {}", options.iter().map(|o| format!("\"{o}\"")).collect::<Vec<_>>().join(" | ")),
+    };
+    Some(format!("expected {expected}, got {value}"))
+}
+
+/// `#rgb[a]`, `#rrggbb[aa]`, `rgb[a](..)` or `hsl[a](..)`.
+fn is_css_color_literal(color: &str) -> bool {
+    let color = color.trim();
+    if let Some(hex) = color.strip_prefix('#') {
+        return matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit());
+    }
+    ["rgb(", "rgba(", "hsl(", "hsla("]
+        .iter()
+        .any(|f| color.to_ascii_lowercase().starts_with(f) && color.ends_with(')'))
+}
+
+/// Entries of `components: { .. }` in the first `callee(_, { components })` call.
+fn define_call_components(
+    text: &str,
+    source_type: SourceType,
+    callee: &str,
+) -> std::result::Result<Option<BTreeMap<String, UiComponent>>, String> {
+    let allocator = Allocator::default();
+    let ret = JsParser::new(&allocator, text, source_type).parse();
+    if ret.panicked {
+        return Err(ret.errors.first().map(|e| e.to_string()).unwrap_or_default());
+    }
+
+    let mut visitor = DefineCallVisitor { callee, components: None };
+    visitor.visit_program(&ret.program);
+    Ok(visitor.components)
+}
+
+struct DefineCallVisitor<'s> {
+    callee: &'s str,
+    components: Option<BTreeMap<String, UiComponent>>,
+}
+
+impl<'a> Visit<'a> for DefineCallVisitor<'_> {
+    fn visit_call_expression(&mut self, it: &CallExpression<'a>) {
+        if self.components.is_none() && it.callee_name() == Some(self.callee) {
+            let options = it.arguments.get(1).and_then(|a| a.as_expression());
+            if let Some(Expression::ObjectExpression(options)) = options {
+                if let Some(Expression::ObjectExpression(components)) = object_property(options, "components") {
+                    let components = components
+                        .properties
+                        .iter()
+                        .filter_map(|p| match p {
+                            ObjectPropertyKind::ObjectProperty(p) => {
+                                Some((p.key.static_name()?.to_string(), ui_component(&p.value)))
+                            }
+                            ObjectPropertyKind::SpreadProperty(_) => None,
+                        })
+                        .collect();
+                    self.components = Some(components);
+                    return;
+                }
+            }
+        }
+        walk::walk_call_expression(self, it);
+    }
+}
+
+/// Value of the statically named property `name` in an object literal.
+fn object_property<'b, 'a>(object: &'b ObjectExpression<'a>, name: &str) -> Option<&'b Expression<'a>> {
+    object.properties.iter().find_map(|p| match p {
+        ObjectPropertyKind::ObjectProperty(p) if p.key.static_name().as_deref() == Some(name) => Some(&p.value),
+        _ => None,
+    })
+}
+
+/// `{ props: z.object({..}), slots: [..] }`.
+fn ui_component(value: &Expression) -> UiComponent {
+    let mut component = UiComponent::default();
+    let Expression::ObjectExpression(object) = value else {
+        return component;
+    };
+    component.slots =
+        matches!(object_property(object, "slots"), Some(Expression::ArrayExpression(slots)) if !slots.elements.is_empty());
+
+    let shape = object_property(object, "props")
+        .and_then(zod_chain)
+        .filter(|(base, _, _)| *base == "object")
+        .and_then(|(_, call, _)| call.arguments.first().and_then(|a| a.as_expression()));
+    if let Some(Expression::ObjectExpression(shape)) = shape {
+        for property in shape.properties.iter() {
+            let ObjectPropertyKind::ObjectProperty(property) = property else {
+                continue;
+            };
+            if let Some(name) = property.key.static_name() {
+                component.props.insert(name.to_string(), zod_prop(&property.value));
+            }
+        }
+    }
+    component
+}
+
+/// Walks a zod chain (`z.number().min(0).optional()`) down to its `z.<base>(..)` call.
+/// Returns the base name, the base call and the modifiers applied on top.
+fn zod_chain<'b, 'a>(expr: &'b Expression<'a>) -> Option<(&'b str, &'b CallExpression<'a>, Vec<&'b str>)> {
+    let mut modifiers = Vec::new();
+    let mut current = expr;
+    loop {
+        let Expression::CallExpression(call) = current else {
+            return None;
+        };
+        let Expression::StaticMemberExpression(member) = &call.callee else {
+            return None;
+        };
+        let name = member.property.name.as_str();
+        match &member.object {
+            Expression::Identifier(object) if object.name == "z" => return Some((name, call, modifiers)),
+            object => {
+                modifiers.push(name);
+                current = object;
+            }
+        }
+    }
+}
+
+fn zod_prop(expr: &Expression) -> ZodProp {
+    let Some((base, call, modifiers)) = zod_chain(expr) else {
+        return ZodProp { kind: ZodKind::Other, optional: true, nullable: true };
+    };
+    let kind = match base {
+        "string" => ZodKind::String,
+        "number" => ZodKind::Number,
+        "boolean" => ZodKind::Boolean,
+        "enum" => match call.arguments.first().and_then(|a| a.as_expression()) {
+            Some(Expression::ArrayExpression(options)) => ZodKind::Enum(
+                options
+                    .elements
+                    .iter()
+                    .filter_map(|o| match o.as_expression() {
+                        Some(Expression::StringLiteral(s)) => Some(s.value.to_string()),
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            _ => ZodKind::Other,
+        },
+        _ => ZodKind::Other,
+    };
+    let has = |names: &[&str]| modifiers.iter().any(|m| names.contains(m));
+    ZodProp {
+        kind,
+        optional: has(&["optional", "nullish", "default"]),
+        nullable: has(&["nullable", "nullish"]),
+    }
+}
+
+/// Config keys `createBaseMachine` handles itself, before the effect's `setConfig`.
+const EFFECT_BASE_CONFIG_KEYS: &[&str] = &["speed", "ledCount"];
+
+/// What an effect.ts accepts: keys its `setConfig` handler compares against and
+/// the fields of its `*EffectConfig` interface (minus `EffectBaseConfig`).
+#[derive(Debug, Default)]
+struct EffectConfigKeys {
+    handled: BTreeSet<String>,
+    fields: BTreeSet<String>,
+}
+
+fn extract_effect_config_keys(text: &str) -> std::result::Result<EffectConfigKeys, String> {
+    let allocator = Allocator::default();
+    let ret = JsParser::new(&allocator, text, SourceType::ts()).parse();
+    if ret.panicked {
+        return Err(ret.errors.first().map(|e| e.to_string()).unwrap_or_default());
+    }
+
+    let mut visitor = EffectConfigVisitor::default();
+    visitor.visit_program(&ret.program);
+    Ok(visitor.keys)
+}
+
+#[derive(Default)]
+struct EffectConfigVisitor {
+    keys: EffectConfigKeys,
+}
+
+impl<'a> Visit<'a> for EffectConfigVisitor {
+    fn visit_object_property(&mut self, it: &ObjectProperty<'a>) {
+        if it.key.static_name().as_deref() == Some("setConfig") {
+            let param = match &it.value {
+                Expression::FunctionExpression(f) => f.params.items.first(),
+                Expression::ArrowFunctionExpression(f) => f.params.items.first(),
+                _ => None,
+            };
+            if let Some(param) = param.and_then(|p| p.pattern.get_identifier_name()) {
+                let mut compares = KeyCompareVisitor { param: param.as_str(), keys: BTreeSet::new() };
+                compares.visit_expression(&it.value);
+                self.keys.handled.extend(compares.keys);
+            }
+        }
+        walk::walk_object_property(self, it);
+    }
+
+    fn visit_ts_interface_declaration(&mut self, it: &TSInterfaceDeclaration<'a>) {
+        if it.id.name.ends_with("EffectConfig") {
+            for signature in it.body.body.iter() {
+                if let TSSignature::TSPropertySignature(property) = signature {
+                    if let Some(name) = property.key.static_name() {
+                        if !EFFECT_BASE_CONFIG_KEYS.contains(&name.as_ref()) {
+                            self.keys.fields.insert(name.to_string());
+                        }
+                    }
+                }
+            }
+        }
+        walk::walk_ts_interface_declaration(self, it);
+    }
+}
+
+/// String literals compared with `param` (`key === 'x'`, `case 'x':`).
+struct KeyCompareVisitor<'s> {
+    param: &'s str,
+    keys: BTreeSet<String>,
+}
+
+impl<'a> Visit<'a> for KeyCompareVisitor<'_> {
+    fn visit_binary_expression(&mut self, it: &BinaryExpression<'a>) {
+        if matches!(it.operator, BinaryOperator::StrictEquality | BinaryOperator::Equality) {
+            match (&it.left, &it.right) {
+                (Expression::Identifier(id), Expression::StringLiteral(key))
+                | (Expression::StringLiteral(key), Expression::Identifier(id))
+                    if id.name == self.param =>
+                {
+                    self.keys.insert(key.value.to_string());
+                }
+                _ => {}
+            }
+        }
+        walk::walk_binary_expression(self, it);
+    }
+
+    fn visit_switch_statement(&mut self, it: &SwitchStatement<'a>) {
+        if matches!(&it.discriminant, Expression::Identifier(id) if id.name == self.param) {
+            for case in it.cases.iter() {
+                if let Some(Expression::StringLiteral(key)) = &case.test {
+                    self.keys.insert(key.value.to_string());
+                }
+            }
+        }
+        walk::walk_switch_statement(self, it);
+    }
+}
+
+/// `(state key, setConfig key)` pairs the UI state bridge sends for a ui.json:
+/// `bridge.colorKeys` collapse into `color`, everything else is sent as-is.
+fn ui_config_keys(ui: &serde_json::Value) -> Vec<(String, String)> {
+    let color_keys: BTreeSet<&str> = ui
+        .pointer("/bridge/colorKeys")
+        .and_then(serde_json::Value::as_array)
+        .map(|keys| keys.iter().filter_map(serde_json::Value::as_str).collect())
+        .unwrap_or_default();
+    let Some(state) = ui.get("state").and_then(|s| s.get(EFFECT_UI_STATE_SCOPE)).and_then(serde_json::Value::as_object)
+    else {
+        return Vec::new();
+    };
+    state
+        .keys()
+        .map(|key| {
+            let sent = if color_keys.contains(key.as_str()) { "color" } else { key.as_str() };
+            (key.clone(), sent.to_string())
+        })
+        .collect()
+}
+
+/// Runtime bundled into every compiled effect (`just compile-effects`).
+const EFFECT_RUNTIME_FILE: &str = "effect-runtime.ts";
+
+/// Ambient declarations of the effect runtime globals.
+const EFFECT_GLOBALS_FILE: &str = "globals.d.ts";
+
+/// `docs/effects_mcu.json5`: what effect sources may use in the MCU build.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EffectsMcuRules {
+    default: String,
+    targets: BTreeMap<String, JsEngineProfile>,
+    globals: BTreeMap<String, ApiSupport>,
+    #[serde(default)]
+    methods: BTreeMap<String, ApiSupport>,
+}
+
+impl EffectsMcuRules {
+    /// Load the rules and pick the named compile target (or the default).
+    fn load(repo_root: &Path, target: Option<&str>) -> Result<(Self, String)> {
+        let path = repo_root.join("docs").join("effects_mcu.json5");
+        let raw = fs::read_to_string(&path).with_context(|| format!("read {}", path.display()))?;
+        let rules: Self = json5::from_str(&raw).with_context(|| format!("parse {}", path.display()))?;
+
+        let target = target.map(str::to_string).unwrap_or_else(|| rules.default.clone());
+        let Some(profile) = rules.targets.get(&target) else {
+            return Err(anyhow!("unknown MCU compile target `{target}` (see {})", path.display()));
+        };
+        for feature in profile.allow.iter().chain(profile.deny.iter()) {
+            if !ES_FEATURES.iter().any(|(id, _, _)| id == feature) {
+                return Err(anyhow!("target `{target}` in {}: unknown feature `{feature}`", path.display()));
+            }
+        }
+        Ok((rules, target))
+    }
+}
+
+fn check_effects_mcu(frontend_root: &Path, repo_root: &Path, target: Option<&str>) -> Result<bool> {
+    let effects_dir = frontend_root.join(EFFECTS_DIR);
+    let globals_path = effects_dir.join(EFFECT_GLOBALS_FILE);
+    let Ok(globals_text) = fs::read_to_string(&globals_path) else {
+        println!("[webf-effects-mcu] skipped: no {EFFECTS_DIR}/{EFFECT_GLOBALS_FILE}");
+        return Ok(true);
+    };
+    let (rules, target) = EffectsMcuRules::load(repo_root, target)?;
+    let profile = &rules.targets[&target];
+
+    let declared_globals = match effect_global_declarations(&globals_text) {
+        Ok(names) => names,
+        Err(e) => {
+            eprintln!("[webf-effects-mcu] {}: could not parse: {e}", pathdiff(frontend_root, &globals_path));
+            return Ok(false);
+        }
+    };
+
+    let mut files = vec![effects_dir.join(EFFECT_RUNTIME_FILE)];
+    let mut effect_files: Vec<PathBuf> = fs::read_dir(&effects_dir)
+        .with_context(|| format!("read {}", effects_dir.display()))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path().join("effect.ts"))
+        .filter(|p| p.is_file())
+        .collect();
+    effect_files.sort();
+    files.extend(effect_files);
+
+    let runtime_path = effects_dir.join(EFFECT_RUNTIME_FILE);
+    let runtime_rel = pathdiff(frontend_root, &runtime_path);
+    let runtime_text = fs::read_to_string(&runtime_path).ok();
+    let runtime = runtime_text.as_deref().map(|text| (runtime_rel.as_str(), text));
+
+    let mut hits: Vec<(String, usize, usize, String, String)> = Vec::new();
+    let mut checked = 0usize;
+    for path in files.iter().filter(|p| p.is_file()) {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("read {}", path.display()))?;
+        if text.contains("webf-effects-mcu:disable") {
+            continue;
+        }
+        checked += 1;
+        let rel = pathdiff(frontend_root, path);
+
+        let found = match mcu_violations(&text, &declared_globals, &rules)
+            .and_then(|mut found| {
+                found.extend(tick_allocations(&text, &rel, runtime)?);
+                Ok(found)
+            }) {
+            Ok(found) => found,
+            Err(e) => {
+                hits.push((rel, 1, 1, "parse error".to_string(), e));
+                continue;
+            }
+        };
+        let features = detect_es_features(&text, SourceType::ts()).unwrap_or_default();
+        let features = features.into_iter().filter_map(|(offset, feature)| {
+            let (_, edition, description) = ES_FEATURES.iter().find(|(id, _, _)| *id == feature)?;
+            profile.rejects(feature, *edition).then(|| {
+                (offset, description.to_string(), format!("{feature}, ES{edition}; target `{target}` is ES{}", profile.es))
+            })
+        });
+
+        for (offset, what, reason) in found.into_iter().chain(features) {
+            if line_text(&text, offset).contains("webf-effects-mcu:ignore") {
+                continue;
+            }
+            let (line, col) = line_col_from_index(&text, offset);
+            hits.push((rel.clone(), line, col, what, reason));
+        }
+    }
+    hits.sort_by(|a, b| (&a.0, a.1, a.2).cmp(&(&b.0, b.1, b.2)));
+
+    if hits.is_empty() {
+        println!("[webf-effects-mcu] OK ({checked} files, target `{target}`)");
+        return Ok(true);
+    }
+
+    eprintln!("[webf-effects-mcu] Found {} constructs the MCU build (target `{target}`) cannot run:", hits.len());
+    for (file, line, col, what, reason) in hits.iter().take(50) {
+        eprintln!("- {file}:{line}:{col}  {what}  ({reason})");
+    }
+    if hits.len() > 50 {
+        eprintln!("...and {} more", hits.len() - 50);
+    }
+    eprintln!("\nThe rules live in docs/effects_mcu.json5 and {EFFECTS_DIR}/{EFFECT_GLOBALS_FILE}. To silence one line, add:");
+    eprintln!("  // webf-effects-mcu:ignore");
+
+    Ok(false)
+}
+
+/// Names declared inside `declare global { .. }` of globals.d.ts.
+fn effect_global_declarations(text: &str) -> std::result::Result<BTreeSet<String>, String> {
+    let allocator = Allocator::default();
+    let ret = JsParser::new(&allocator, text, SourceType::d_ts()).parse();
+    if ret.panicked {
+        return Err(ret.errors.first().map(|e| e.to_string()).unwrap_or_default());
+    }
+
+    let mut visitor = GlobalDeclarationVisitor::default();
+    visitor.visit_program(&ret.program);
+    Ok(visitor.names)
+}
+
+#[derive(Default)]
+struct GlobalDeclarationVisitor {
+    names: BTreeSet<String>,
+}
+
+impl<'a> Visit<'a> for GlobalDeclarationVisitor {
+    fn visit_ts_global_declaration(&mut self, it: &TSGlobalDeclaration<'a>) {
+        for statement in it.body.body.iter() {
+            match statement {
+                Statement::FunctionDeclaration(f) => self.names.extend(f.id.as_ref().map(|id| id.name.to_string())),
+                Statement::VariableDeclaration(v) => {
+                    for declarator in v.declarations.iter() {
+                        self.names.extend(declarator.id.get_identifier_name().map(|n| n.to_string()));
+                    }
+                }
+                Statement::ClassDeclaration(c) => self.names.extend(c.id.as_ref().map(|id| id.name.to_string())),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// MCU rule violations in one effect source: undeclared or unavailable globals,
+/// allocating methods, async functions, generators and classes.
+fn mcu_violations(
+    text: &str,
+    declared_globals: &BTreeSet<String>,
+    rules: &EffectsMcuRules,
+) -> std::result::Result<Vec<(usize, String, String)>, String> {
+    let allocator = Allocator::default();
+    let ret = JsParser::new(&allocator, text, SourceType::ts()).parse();
+    if ret.panicked {
+        return Err(ret.errors.first().map(|e| e.to_string()).unwrap_or_default());
+    }
+
+    let mut bindings = BindingNameVisitor::default();
+    bindings.visit_program(&ret.program);
+    let mut visitor = McuVisitor { declared: &bindings.names, declared_globals, rules, found: Vec::new() };
+    visitor.visit_program(&ret.program);
+    Ok(visitor.found)
+}
+
+/// Every name bound anywhere in a file (functions, variables, parameters).
+#[derive(Default)]
+struct BindingNameVisitor {
+    names: BTreeSet<String>,
+}
+
+impl<'a> Visit<'a> for BindingNameVisitor {
+    fn visit_binding_identifier(&mut self, it: &BindingIdentifier<'a>) {
+        self.names.insert(it.name.to_string());
+    }
+}
+
+struct McuVisitor<'r> {
+    declared: &'r BTreeSet<String>,
+    declared_globals: &'r BTreeSet<String>,
+    rules: &'r EffectsMcuRules,
+    found: Vec<(usize, String, String)>,
+}
+
+impl McuVisitor<'_> {
+    fn check_function(&mut self, it: &Function) {
+        if it.r#async {
+            self.found.push((it.span.start as usize, "async function".to_string(), "no event loop on the MCU".to_string()));
+        }
+        if it.generator {
+            self.found.push((it.span.start as usize, "generator".to_string(), "not in the mquickjs build".to_string()));
+        }
+    }
+}
+
+impl<'a> Visit<'a> for McuVisitor<'_> {
+    // Types are erased by the compile step; only runtime references count.
+    fn visit_ts_type(&mut self, _it: &TSType<'a>) {}
+
+    fn visit_ts_interface_declaration(&mut self, _it: &TSInterfaceDeclaration<'a>) {}
+
+    fn visit_identifier_reference(&mut self, it: &IdentifierReference<'a>) {
+        let name = it.name.as_str();
+        if self.declared.contains(name) || self.declared_globals.contains(name) {
+            return;
+        }
+        let reason = match self.rules.globals.get(name) {
+            Some(support) => support.missing_reason().map(str::to_string),
+            None => Some(format!("not declared in {EFFECTS_DIR}/{EFFECT_GLOBALS_FILE}")),
+        };
+        if let Some(reason) = reason {
+            self.found.push((it.span.start as usize, format!("`{name}`"), reason));
+        }
+    }
+
+    fn visit_statement(&mut self, it: &Statement<'a>) {
+        if let Statement::FunctionDeclaration(f) = it {
+            self.check_function(f);
+        }
+        walk::walk_statement(self, it);
+    }
+
+    fn visit_expression(&mut self, it: &Expression<'a>) {
+        if let Expression::FunctionExpression(f) = it {
+            self.check_function(f);
+        }
+        walk::walk_expression(self, it);
+    }
+
+    fn visit_arrow_function_expression(&mut self, it: &ArrowFunctionExpression<'a>) {
+        if it.r#async {
+            self.found.push((it.span.start as usize, "async function".to_string(), "no event loop on the MCU".to_string()));
+        }
+        walk::walk_arrow_function_expression(self, it);
+    }
+
+    fn visit_class(&mut self, it: &Class<'a>) {
+        self.found.push((
+            it.span.start as usize,
+            "class".to_string(),
+            "not in the MCU build; use a factory function".to_string(),
+        ));
+        walk::walk_class(self, it);
+    }
+
+    fn visit_call_expression(&mut self, it: &CallExpression<'a>) {
+        if let Expression::StaticMemberExpression(member) = it.callee.without_parentheses() {
+            let method = member.property.name.as_str();
+            if let Some(reason) = self.rules.methods.get(method).and_then(ApiSupport::missing_reason) {
+                self.found.push((member.property.span.start as usize, format!(".{method}()"), reason.to_string()));
+            }
+        }
+        walk::walk_call_expression(self, it);
+    }
+}
+
+/// Allocation sites and calls by name inside one function body.
+#[derive(Debug, Default)]
+struct AllocationFacts {
+    allocations: Vec<(usize, String)>,
+    calls: Vec<(usize, String)>,
+}
+
+impl AllocationFacts {
+    fn of_body(body: &FunctionBody) -> Self {
+        let mut visitor = AllocationVisitor::default();
+        visitor.visit_function_body(body);
+        visitor.facts
+    }
+}
+
+#[derive(Default)]
+struct AllocationVisitor {
+    facts: AllocationFacts,
+}
+
+impl<'a> Visit<'a> for AllocationVisitor {
+    fn visit_ts_type(&mut self, _it: &TSType<'a>) {}
+
+    fn visit_statement(&mut self, it: &Statement<'a>) {
+        // A nested function is a closure created on every call; its body runs elsewhere.
+        if let Statement::FunctionDeclaration(f) = it {
+            self.facts.allocations.push((f.span.start as usize, "closure".to_string()));
+            return;
+        }
+        walk::walk_statement(self, it);
+    }
+
+    fn visit_expression(&mut self, it: &Expression<'a>) {
+        let allocation = match it {
+            Expression::FunctionExpression(f) => {
+                self.facts.allocations.push((f.span.start as usize, "closure".to_string()));
+                return;
+            }
+            Expression::ArrowFunctionExpression(f) => {
+                self.facts.allocations.push((f.span.start as usize, "closure".to_string()));
+                return;
+            }
+            Expression::NewExpression(new) => Some((
+                new.span,
+                match new.callee.without_parentheses() {
+                    Expression::Identifier(id) => format!("new {}", id.name),
+                    _ => "new".to_string(),
+                },
+            )),
+            Expression::ArrayExpression(array) => Some((array.span, "array literal".to_string())),
+            Expression::ObjectExpression(object) => Some((object.span, "object literal".to_string())),
+            Expression::CallExpression(call) => {
+                if let Expression::Identifier(id) = call.callee.without_parentheses() {
+                    self.facts.calls.push((call.span.start as usize, id.name.to_string()));
+                }
+                None
+            }
+            _ => None,
+        };
+        if let Some((span, what)) = allocation {
+            self.facts.allocations.push((span.start as usize, what));
+        }
+        walk::walk_expression(self, it);
+    }
+}
+
+/// A named function (`function f`, `const f = () => ..`) and what it allocates.
+struct FunctionEntry {
+    name: String,
+    /// Offset of the declaration.
+    start: usize,
+    /// Byte range of the enclosing function (or the whole file) it is visible in.
+    scope: (usize, usize),
+    facts: AllocationFacts,
+}
+
+/// Named functions of a file, keyed by name and enclosing scope.
+#[derive(Default)]
+struct FunctionTableVisitor {
+    functions: Vec<FunctionEntry>,
+    scopes: Vec<(usize, usize)>,
+    tick: Option<AllocationFacts>,
+}
+
+impl FunctionTableVisitor {
+    fn add(&mut self, name: &str, start: usize, body: &FunctionBody) {
+        let scope = self.scopes.last().copied().unwrap_or((0, usize::MAX));
+        self.functions.push(FunctionEntry { name: name.to_string(), start, scope, facts: AllocationFacts::of_body(body) });
+    }
+
+    /// The function `name` refers to at `offset`: the innermost enclosing scope
+    /// wins, and within one scope the last declaration (as in JS).
+    fn resolve(&self, name: &str, offset: usize) -> Option<&FunctionEntry> {
+        self.functions
+            .iter()
+            .rev()
+            .filter(|f| f.name == name && f.scope.0 <= offset && offset < f.scope.1)
+            .min_by_key(|f| f.scope.1 - f.scope.0)
+    }
+
+    fn in_scope(&mut self, span: oxc_span::Span, walk: impl FnOnce(&mut Self)) {
+        self.scopes.push((span.start as usize, span.end as usize));
+        walk(self);
+        self.scopes.pop();
+    }
+}
+
+impl<'a> Visit<'a> for FunctionTableVisitor {
+    fn visit_statement(&mut self, it: &Statement<'a>) {
+        if let Statement::FunctionDeclaration(f) = it {
+            if let (Some(id), Some(body)) = (&f.id, &f.body) {
+                self.add(&id.name, f.span.start as usize, body);
+            }
+            self.in_scope(f.span, |v| walk::walk_statement(v, it));
+            return;
+        }
+        walk::walk_statement(self, it);
+    }
+
+    fn visit_expression(&mut self, it: &Expression<'a>) {
+        match it {
+            Expression::FunctionExpression(f) => self.in_scope(f.span, |v| walk::walk_expression(v, it)),
+            Expression::ArrowFunctionExpression(f) => self.in_scope(f.span, |v| walk::walk_expression(v, it)),
+            _ => walk::walk_expression(self, it),
+        }
+    }
+
+    fn visit_variable_declarator(&mut self, it: &VariableDeclarator<'a>) {
+        if let Some(name) = it.id.get_identifier_name() {
+            let body = match &it.init {
+                Some(Expression::FunctionExpression(f)) => f.body.as_deref(),
+                Some(Expression::ArrowFunctionExpression(f)) => Some(&*f.body),
+                _ => None,
+            };
+            if let Some(body) = body {
+                self.add(&name, it.span.start as usize, body);
+            }
+        }
+        walk::walk_variable_declarator(self, it);
+    }
+
+    fn visit_call_expression(&mut self, it: &CallExpression<'a>) {
+        if self.tick.is_none() && it.callee_name() == Some("createBaseMachine") {
+            if let Some(Expression::ObjectExpression(handlers)) = it.arguments.get(2).and_then(|a| a.as_expression()) {
+                self.tick = match object_property(handlers, "tick") {
+                    Some(Expression::FunctionExpression(f)) => f.body.as_deref().map(AllocationFacts::of_body),
+                    Some(Expression::ArrowFunctionExpression(f)) => Some(AllocationFacts::of_body(&f.body)),
+                    // `tick: step` - resolved against the function table.
+                    Some(Expression::Identifier(id)) => Some(AllocationFacts {
+                        allocations: Vec::new(),
+                        calls: vec![(id.span.start as usize, id.name.to_string())],
+                    }),
+                    _ => None,
+                };
+            }
+        }
+        walk::walk_call_expression(self, it);
+    }
+}
+
+fn function_table(text: &str) -> std::result::Result<FunctionTableVisitor, String> {
+    let allocator = Allocator::default();
+    let ret = JsParser::new(&allocator, text, SourceType::ts()).parse();
+    if ret.panicked {
+        return Err(ret.errors.first().map(|e| e.to_string()).unwrap_or_default());
+    }
+
+    let mut visitor = FunctionTableVisitor::default();
+    visitor.visit_program(&ret.program);
+    Ok(visitor)
+}
+
+/// Allocations reachable from the `tick` handler an effect passes to
+/// `createBaseMachine`, following calls into its own functions and the runtime.
+/// Each call is reported at its site in `tick`, with the first allocation it reaches.
+fn tick_allocations(
+    text: &str,
+    rel: &str,
+    runtime: Option<(&str, &str)>,
+) -> std::result::Result<Vec<(usize, String, String)>, String> {
+    let effect = function_table(text)?;
+    let runtime_table = match runtime {
+        Some((runtime_rel, runtime_text)) if runtime_rel != rel => function_table(runtime_text)?,
+        _ => FunctionTableVisitor::default(),
+    };
+
+    let Some(tick) = effect.tick.as_ref() else {
+        return Ok(Vec::new());
+    };
+
+    // First allocation reachable from a call to `name` at `offset`: (in runtime, offset, what).
+    fn first_allocation(
+        name: &str,
+        offset: usize,
+        from_runtime: bool,
+        effect: &FunctionTableVisitor,
+        runtime: &FunctionTableVisitor,
+        visiting: &mut BTreeSet<(bool, usize)>,
+    ) -> Option<(bool, usize, String)> {
+        // Calls out of a runtime function only resolve inside the runtime; the
+        // effect falls back to the runtime's top-level functions.
+        let local = if from_runtime { None } else { effect.resolve(name, offset) };
+        let (in_runtime, entry) = match local {
+            Some(entry) => (false, entry),
+            None => (true, runtime.resolve(name, if from_runtime { offset } else { 0 })?),
+        };
+        if !visiting.insert((in_runtime, entry.start)) {
+            return None;
+        }
+        if let Some((at, what)) = entry.facts.allocations.first() {
+            return Some((in_runtime, *at, what.clone()));
+        }
+        entry
+            .facts
+            .calls
+            .iter()
+            .find_map(|(at, callee)| first_allocation(callee, *at, in_runtime, effect, runtime, visiting))
+    }
+
+    let mut out: Vec<(usize, String, String)> = tick
+        .allocations
+        .iter()
+        .map(|(offset, what)| (*offset, what.clone(), "allocates on every tick; move it into createEffect".to_string()))
+        .collect();
+    for (offset, name) in tick.calls.iter() {
+        let Some((in_runtime, at, what)) =
+            first_allocation(name, *offset, false, &effect, &runtime_table, &mut BTreeSet::new())
+        else {
+            continue;
+        };
+        let (file, file_text) = match (in_runtime, runtime) {
+            (true, Some((runtime_rel, runtime_text))) => (runtime_rel.to_string(), runtime_text),
+            _ => (rel.to_string(), text),
+        };
+        let (line, col) = line_col_from_index(file_text, at);
+        out.push((*offset, format!("{name}()"), format!("allocates on every tick: {what} at {file}:{line}:{col}")));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tick_allocation_tests {
+    use super::tick_allocations;
+
+    const RUNTIME: &str = "function hsvToRgb(h: number) { return [h, h, h]; }\n\
+                           function clamp(v: number) { return v; }\n";
+
+    fn whats(effect: &str) -> Vec<(String, String)> {
+        tick_allocations(effect, "fx/effect.ts", Some(("effect-runtime.ts", RUNTIME)))
+            .unwrap()
+            .into_iter()
+            .map(|(_, what, why)| (what, why))
+            .collect()
+    }
+
+    #[test]
+    fn follows_calls_into_the_runtime() {
+        let found = whats("createBaseMachine(1, 1, { tick: function () { hsvToRgb(1); clamp(2); } });");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, "hsvToRgb()");
+        assert!(found[0].1.contains("effect-runtime.ts:1:"), "{found:?}");
+    }
+
+    #[test]
+    fn resolves_nested_functions_by_scope() {
+        // `step` inside createEffect allocates; the unrelated top-level `step` does not.
+        let effect = "function step() { return 1; }\n\
+                      function createEffect() {\n\
+                        function step() { return [1]; }\n\
+                        return createBaseMachine(1, 1, { tick: function () { step(); } });\n\
+                      }\n\
+                      function other() { function step() { return 2; } }\n";
+        let found = whats(effect);
+        assert_eq!(found.len(), 1, "{found:?}");
+        assert_eq!(found[0].0, "step()");
+        assert!(found[0].1.contains("array literal at fx/effect.ts:3:"), "{found:?}");
+    }
+
+    #[test]
+    fn last_declaration_in_a_scope_wins() {
+        // Only the last body runs, and it does not allocate.
+        let effect = "function pick() { return [1]; }\n\
+                      function pick() { return 1; }\n\
+                      createBaseMachine(1, 1, { tick: function () { pick(); } });\n";
+        assert!(whats(effect).is_empty());
+    }
+
+    #[test]
+    fn effect_functions_take_precedence_over_the_runtime() {
+        let found = whats("function clamp(v: number) { return [v]; }\n\
+                           createBaseMachine(1, 1, { tick: function () { clamp(1); } });\n");
+        assert_eq!(found.len(), 1, "{found:?}");
+        assert!(found[0].0 == "clamp()" && found[0].1.contains("fx/effect.ts:1:"), "{found:?}");
+    }
+}
+
+/// Dart dirs (relative to the repo root) searched for WebF module classes and
+/// their TS wrappers.
+const NATIVE_MODULE_SOURCE_DIRS: &[&str] = &["lib", "webfly_packages"];
+
+/// Registered module classes whose Dart source lives in a pub package, with
+/// their module name. Only the module name is checked for these.
+const EXTERNAL_NATIVE_MODULES: &[(&str, &str)] = &[("SQFliteModule", "SQFlite")];
+
+/// A WebF module class found in Dart sources.
+#[derive(Debug)]
+struct DartModule {
+    class: String,
+    name: String,
+    file: PathBuf,
+    /// Method name -> 1-based line of its dispatch case.
+    methods: BTreeMap<String, usize>,
+    events: BTreeSet<String>,
+    /// Byte range of the class body (`{` to `}`).
+    body: (usize, usize),
+}
+
+/// A native module reference in TS: `module.method` call or `Module:event` name.
+#[derive(Debug)]
+struct NativeCallSite {
+    file: String,
+    line: usize,
+    col: usize,
+    module: String,
+    member: String,
+    is_event: bool,
+}
+
+/// Dart module classes, their registrations and the files next to them.
+struct NativeModuleSources {
+    files: Vec<PathBuf>,
+    /// Classes passed to `WebF.defineModule`.
+    registered: BTreeSet<String>,
+    modules: Vec<DartModule>,
+}
+
+impl NativeModuleSources {
+    fn load(repo_root: &Path) -> Result<Self> {
+        let source_dirs: Vec<PathBuf> = NATIVE_MODULE_SOURCE_DIRS.iter().map(|d| repo_root.join(d)).collect();
+        let files = walk_files(&source_dirs);
+
+        let define_re = Regex::new(r"WebF\.defineModule\(\s*\(\s*\w+\s*\)\s*=>\s*(\w+)\s*\(").unwrap();
+        let mut registered = BTreeSet::new();
+        let mut modules = Vec::new();
+        for path in files.iter().filter(|p| p.extension().and_then(|e| e.to_str()) == Some("dart")) {
+            let text = fs::read_to_string(path)
+                .with_context(|| format!("read {}", path.display()))?;
+            registered.extend(define_re.captures_iter(&text).map(|caps| caps[1].to_string()));
+            modules.extend(parse_dart_modules(path, &text));
+        }
+
+        Ok(Self { files, registered, modules })
+    }
+
+    /// Registered classes with neither a Dart source nor an external entry.
+    fn missing_classes(&self) -> Vec<&str> {
+        self.registered
+            .iter()
+            .map(String::as_str)
+            .filter(|class| {
+                !self.modules.iter().any(|m| m.class == *class)
+                    && !EXTERNAL_NATIVE_MODULES.iter().any(|(c, _)| c == class)
+            })
+            .collect()
+    }
+
+    fn registered_modules(&self) -> Vec<&DartModule> {
+        self.modules.iter().filter(|m| self.registered.contains(&m.class)).collect()
+    }
+
+    /// Module names of registered external modules.
+    fn external_names(&self) -> BTreeSet<&'static str> {
+        EXTERNAL_NATIVE_MODULES
+            .iter()
+            .filter(|(class, _)| self.registered.contains(*class))
+            .map(|(_, name)| *name)
+            .collect()
+    }
+
+    /// TS sources that may talk to native modules: the project files plus the
+    /// wrappers next to the Dart modules (`.d.ts` excluded).
+    fn ts_files<'a>(&'a self, scan_files: &'a ScanFiles) -> BTreeSet<&'a Path> {
+        let mut ts_files: BTreeSet<&Path> = scan_files
+            .with_extensions(&["ts", "tsx", "js", "jsx"])
+            .map(PathBuf::as_path)
+            .collect();
+        ts_files.extend(
+            self.files
+                .iter()
+                .filter(|p| matches!(p.extension().and_then(|e| e.to_str()), Some("ts" | "tsx")))
+                .map(PathBuf::as_path),
+        );
+        ts_files.retain(|p| !p.to_string_lossy().ends_with(".d.ts"));
+        ts_files
+    }
+}
+
+fn check_native_modules(frontend_root: &Path, repo_root: &Path, scan_files: &ScanFiles) -> Result<bool> {
+    let sources = NativeModuleSources::load(repo_root)?;
+    if sources.registered.is_empty() {
+        println!("[webf-native-modules] skipped: no WebF.defineModule registrations found");
+        return Ok(true);
+    }
+    let missing = sources.missing_classes();
+    if !missing.is_empty() {
+        println!(
+            "[webf-native-modules] skipped: Dart sources not found for {} (is webfly_packages checked out?)",
+            missing.join(", ")
+        );
+        return Ok(true);
+    }
+    let modules = sources.registered_modules();
+    let external = sources.external_names();
+
+    let module_names: BTreeSet<&str> = modules.iter().map(|m| m.name.as_str()).collect();
+
+    // Call sites: the project sources plus the TS wrappers next to the Dart modules.
+    let mut calls = Vec::new();
+    for path in sources.ts_files(scan_files) {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("read {}", path.display()))?;
+        calls.extend(extract_native_call_sites(&pathdiff(frontend_root, path), &text, &module_names));
+    }
+
+    let mut hits: Vec<(String, usize, usize, String, String)> = Vec::new();
+    let mut used: BTreeSet<(String, String)> = BTreeSet::new();
+    for call in calls.iter() {
+        let target = format!("{}{}{}", call.module, if call.is_event { ":" } else { "." }, call.member);
+        if external.contains(call.module.as_str()) {
+            continue;
+        }
+        let Some(module) = modules.iter().find(|m| m.name == call.module) else {
+            let known: Vec<&str> = module_names.iter().chain(external.iter()).copied().collect();
+            hits.push((
+                call.file.clone(),
+                call.line,
+                call.col,
+                call.module.clone(),
+                format!("unknown module; registered: {}", known.join(", ")),
+            ));
+            continue;
+        };
+        if call.is_event {
+            if !module.events.contains(&call.member) {
+                hits.push((call.file.clone(), call.line, call.col, target, "unknown event".to_string()));
+            }
+        } else if module.methods.contains_key(&call.member) {
+            used.insert((call.module.clone(), call.member.clone()));
+        } else {
+            hits.push((call.file.clone(), call.line, call.col, target, "unknown method".to_string()));
+        }
+    }
+
+    let unused: Vec<String> = modules
+        .iter()
+        .flat_map(|m| {
+            m.methods
+                .iter()
+                .filter(|(method, _)| !used.contains(&(m.name.clone(), method.to_string())))
+                .map(|(method, line)| format!("- {}.{method}  ({}:{line})", m.name, pathdiff(repo_root, &m.file)))
+        })
+        .collect();
+    if !unused.is_empty() {
+        eprintln!("[webf-native-modules] warning: {} native methods are never called from TS:", unused.len());
+        for line in unused.iter().take(30) {
+            eprintln!("{line}");
+        }
+        if unused.len() > 30 {
+            eprintln!("...and {} more", unused.len() - 30);
+        }
+    }
+
+    hits.sort();
+    if hits.is_empty() {
+        println!("[webf-native-modules] OK");
+        return Ok(true);
+    }
+
+    eprintln!("[webf-native-modules] Found {} native module references with no Dart counterpart:", hits.len());
+    for (file, line, col, target, detail) in hits.iter().take(50) {
+        eprintln!("- {file}:{line}:{col}  {target}  ({detail})");
+    }
+    if hits.len() > 50 {
+        eprintln!("...and {} more", hits.len() - 50);
+    }
+
+    Ok(false)
+}
+
+/// WebF module classes in a Dart file: `name` getter, the method names
+/// dispatched in `invoke(String method, ...)` and the events it dispatches.
+fn parse_dart_modules(path: &Path, text: &str) -> Vec<DartModule> {
+    let class_re = Regex::new(r"\bclass\s+(\w+)\s+extends\s+(?:WebF)?BaseModule\b[^{]*\{").unwrap();
+    let name_re = Regex::new(r#"String\s+get\s+name\s*=>\s*['"](\w+)['"]"#).unwrap();
+    let invoke_re = Regex::new(r"\binvoke\s*\(\s*String\s+(\w+)\s*,").unwrap();
+    let case_re = Regex::new(r#"\bcase\s+['"](\w+)['"]|['"](\w+)['"]\s*=>"#).unwrap();
+    let event_re = Regex::new(r#"\bEvent\(\s*['"]([\w:]+)['"]"#).unwrap();
+
+    let mut modules = Vec::new();
+    for caps in class_re.captures_iter(text) {
+        let open = caps.get(0).unwrap().end() - 1;
+        let close = find_matching_brace(text, open).unwrap_or(text.len() - 1);
+        let body = &text[open..close];
+        let Some(name) = name_re.captures(body).map(|c| c[1].to_string()) else {
+            continue;
+        };
+
+        let mut methods = BTreeMap::new();
+        if let Some(invoke) = invoke_re.captures(body) {
+            let param = invoke[1].to_string();
+            let from = open + invoke.get(0).unwrap().end();
+            let invoke_open = text[from..close].find('{').map(|i| from + i);
+            if let Some(invoke_open) = invoke_open {
+                let invoke_close = find_matching_brace(text, invoke_open).unwrap_or(close);
+                let invoke_body = &text[invoke_open..invoke_close];
+                let eq_re = Regex::new(&format!(r#"\b{param}\s*==\s*['"](\w+)['"]"#)).unwrap();
+                for m in case_re.captures_iter(invoke_body).chain(eq_re.captures_iter(invoke_body)) {
+                    let method = m.get(1).or_else(|| m.get(2)).unwrap();
+                    let (line, _) = line_col_from_index(text, invoke_open + method.start());
+                    methods.entry(method.as_str().to_string()).or_insert(line);
+                }
+            }
+        }
+
+        let prefix = format!("{name}:");
+        let events = event_re
+            .captures_iter(body)
+            .map(|c| c[1].strip_prefix(prefix.as_str()).unwrap_or(&c[1]).to_string())
+            .collect();
+
+        modules.push(DartModule {
+            class: caps[1].to_string(),
+            name,
+            file: path.to_path_buf(),
+            methods,
+            events,
+            body: (open, close),
+        });
+    }
+    modules
+}
+
+#[cfg(test)]
+mod dart_module_tests {
+    use super::parse_dart_modules;
+    use std::path::Path;
+
+    const SOURCE: &str = r#"
+class ThemeModule extends WebFBaseModule {
+  ThemeModule(super.manager);
+
+  @override
+  String get name => 'Theme';
+
+  @override
+  Future<dynamic> invoke(String method, List<dynamic> params) async {
+    switch (method) {
+      case 'getTheme':
+        return returnOk(current);
+      case "setTheme":
+        dispatchEvent(event: Event('Theme:changed'));
+        return null;
+    }
+    if (method == 'reset') return returnOk(null);
+    return returnErr('unknown method');
+  }
+}
+
+class Helper extends BaseModule {
+  // No `name` getter: not a registered module.
+}
+"#;
+
+    #[test]
+    fn parses_methods_events_and_body() {
+        let modules = parse_dart_modules(Path::new("lib/theme.dart"), SOURCE);
+        assert_eq!(modules.len(), 1);
+        let module = &modules[0];
+        assert_eq!((module.class.as_str(), module.name.as_str()), ("ThemeModule", "Theme"));
+        let methods: Vec<(&str, usize)> = module.methods.iter().map(|(m, line)| (m.as_str(), *line)).collect();
+        assert_eq!(methods, [("getTheme", 11), ("reset", 17), ("setTheme", 13)]);
+        assert_eq!(module.events.iter().collect::<Vec<_>>(), ["changed"]);
+        assert!(SOURCE[module.body.0..].starts_with('{') && SOURCE[module.body.1..].starts_with('}'));
+    }
+}
+
+/// Native module references in TS: `invokeWebFModule('Mod', 'method')` and
+/// friends, calls through `createModuleInvoker('Mod')` bindings, and
+/// `'Mod:event'` strings naming a known module.
+fn extract_native_call_sites(file: &str, text: &str, module_names: &BTreeSet<&str>) -> Vec<NativeCallSite> {
+    let invoke_re = Regex::new(
+        r#"\b(?:invokeWebFModule|invokeModule|invokeModuleAsync|invoker)!?\s*(?:<[^>()]*>)?\(\s*['"](\w+)['"]\s*,\s*['"](\w+)['"]"#,
+    )
+    .unwrap();
+    let binding_re = Regex::new(r#"\b(?:const|let|var)\s+(\w+)\s*=\s*createModuleInvoker\(\s*['"](\w+)['"]\s*\)"#).unwrap();
+    let event_re = Regex::new(r#"['"]([A-Z]\w*):([A-Za-z]\w*)['"]"#).unwrap();
+
+    let mut sites = Vec::new();
+    let mut push = |offset: usize, module: &str, member: &str, is_event: bool| {
+        let (line, col) = line_col_from_index(text, offset);
+        sites.push(NativeCallSite {
+            file: file.to_string(),
+            line,
+            col,
+            module: module.to_string(),
+            member: member.to_string(),
+            is_event,
+        });
+    };
+
+    for caps in invoke_re.captures_iter(text) {
+        push(caps.get(1).unwrap().start(), &caps[1], &caps[2], false);
+    }
+    for binding in binding_re.captures_iter(text) {
+        let call_re = Regex::new(&format!(
+            r#"\b{}\s*(?:<[^>()]*>)?\(\s*['"](\w+)['"]"#,
+            regex::escape(&binding[1])
+        ))
+        .unwrap();
+        for caps in call_re.captures_iter(text) {
+            push(caps.get(1).unwrap().start(), &binding[2], &caps[1], false);
+        }
+    }
+    for caps in event_re.captures_iter(text) {
+        if module_names.contains(&caps[1]) {
+            push(caps.get(0).unwrap().start(), &caps[1], &caps[2], true);
+        }
+    }
+
+    sites
+}
+
+/// `docs/webf_protocol.json5`: the TS helper that unwraps `{ result } | { error }`,
+/// the only place allowed to look inside a native response.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProtocolRules {
+    helper: String,
+    /// Relative to the repo root.
+    helper_file: String,
+}
+
+impl ProtocolRules {
+    fn load(repo_root: &Path) -> Result<Self> {
+        let path = repo_root.join("docs").join("webf_protocol.json5");
+        let raw = fs::read_to_string(&path).with_context(|| format!("read {}", path.display()))?;
+        json5::from_str(&raw).with_context(|| format!("parse {}", path.display()))
+    }
+}
+
+/// Dart helpers that build protocol responses (lib/webf/protocol.dart).
+const PROTOCOL_DART_BUILDERS: &[&str] = &["returnOk(", "returnErr(", ".toJson("];
+
+/// Reads of `result`/`error` on values returned by `invokeModule` (or a
+/// `createModuleInvoker` binding): `res.result`, `res?.error.code`,
+/// `'error' in res`, `isWebfError(res)` and `(await invokeModule(...)).result`.
+fn raw_response_accesses(text: &str) -> Vec<(usize, String)> {
+    let binding_re = Regex::new(r#"\b(?:const|let|var)\s+(\w+)\s*=\s*createModuleInvoker\s*\("#).unwrap();
+    let mut invokers = vec!["invokeModule".to_string(), "invokeModuleAsync".to_string()];
+    invokers.extend(binding_re.captures_iter(text).map(|caps| caps[1].to_string()));
+    let callee = format!(
+        r"(?:{})!?\s*(?:<[^>()]*>)?\(",
+        invokers.iter().map(|name| regex::escape(name)).collect::<Vec<_>>().join("|")
+    );
+
+    let response_re = Regex::new(&format!(
+        r"\b(?:const|let|var)\s+(\w+)\s*(?::[^=;]+)?=\s*(?:await\s+)?\b{callee}"
+    ))
+    .unwrap();
+    let mut patterns = vec![format!(r"\(\s*await\s+\b{callee}[^()]*\)\s*\)\s*(?:\?\.|\.)\s*(?:result|error)\b")];
+    for caps in response_re.captures_iter(text) {
+        let name = regex::escape(&caps[1]);
+        patterns.push(format!(r"\b{name}\s*(?:\?\.|\.)\s*(?:result|error)\b"));
+        patterns.push(format!(r#"['"](?:result|error)['"]\s+in\s+{name}\b"#));
+        patterns.push(format!(r"\bisWebfError\s*\(\s*{name}\b"));
+    }
+
+    // Match the raw text (the `'error' in x` form needs the quoted key); the
+    // mask only drops hits in comments and strings, where quotes survive.
+    let code = mask_js_non_code(text);
+    let mut found: Vec<(usize, String)> = Vec::new();
+    for pattern in patterns.iter() {
+        for m in Regex::new(pattern).unwrap().find_iter(text) {
+            if code.as_bytes()[m.start()] != b' ' {
+                found.push((m.start(), m.as_str().split_whitespace().collect::<Vec<_>>().join(" ")));
+            }
+        }
+    }
+    found.sort();
+    found.dedup();
+    found
+}
+
+#[cfg(test)]
+mod raw_response_tests {
+    use super::raw_response_accesses;
+
+    fn accesses(text: &str) -> Vec<String> {
+        raw_response_accesses(text).into_iter().map(|(_, access)| access).collect()
+    }
+
+    #[test]
+    fn flags_reads_of_invoke_results() {
+        let text = "const call = createModuleInvoker('Ble');\n\
+                    const res = await call<Scan>('scan', 1);\n\
+                    if ('error' in res) throw new Error(res.error.message);\n\
+                    const state: State = await invokeModule('Ble', 'state');\n\
+                    return [state?.result, (await invokeModule('Ble', 'x')).result];\n";
+        assert_eq!(
+            accesses(text),
+            ["'error' in res", "res.error", "state?.result", "(await invokeModule('Ble', 'x')).result"]
+        );
+    }
+
+    #[test]
+    fn ignores_unrelated_values() {
+        let text = "const res = await fetch(url);\n\
+                    const data = await invokeModule('Ble', 'scan');\n\
+                    // data.result is unwrapped below\n\
+                    return unwrapWebfResponse(data) ?? res.result ?? job.error.code;\n";
+        assert!(accesses(text).is_empty(), "{:?}", accesses(text));
+    }
+}
+
+fn check_protocol(frontend_root: &Path, repo_root: &Path, scan_files: &ScanFiles) -> Result<bool> {
+    let sources = NativeModuleSources::load(repo_root)?;
+    let external = sources.external_names();
+    let rules = ProtocolRules::load(repo_root)?;
+    let helper = rules.helper.as_str();
+
+    let helper_def_re = Regex::new(&format!(r"\bfunction\s+{}\b", regex::escape(helper))).unwrap();
+
+    let mut hits: Vec<(String, usize, usize, String, String)> = Vec::new();
+    let mut helper_found = false;
+
+    for path in sources.ts_files(scan_files) {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("read {}", path.display()))?;
+        let rel = pathdiff(frontend_root, path);
+
+        if let Some(def) = helper_def_re.find(&text) {
+            helper_found = true;
+            let body = text[def.end()..]
+                .find('{')
+                .and_then(|i| find_matching_brace(&text, def.end() + i).map(|end| &text[def.end() + i..end]))
+                .unwrap_or("");
+            for branch in ["result", "error"] {
+                if !body.contains(branch) {
+                    let (line, col) = line_col_from_index(&text, def.start());
+                    hits.push((
+                        rel.clone(),
+                        line,
+                        col,
+                        helper.to_string(),
+                        format!("helper does not handle the `{branch}` branch"),
+                    ));
+                }
+            }
+            continue;
+        }
+
+        // Wrappers: files that call a JSON-RPC module directly.
+        let calls: Vec<NativeCallSite> = extract_native_call_sites(&rel, &text, &BTreeSet::new())
+            .into_iter()
+            .filter(|c| !external.contains(c.module.as_str()))
+            .collect();
+        let Some(first) = calls.first() else {
+            continue;
+        };
+        if text.contains("webf-protocol:disable") {
+            continue;
+        }
+
+        if !text.contains(helper) {
+            hits.push((
+                rel.clone(),
+                first.line,
+                first.col,
+                format!("{}.{}", first.module, first.member),
+                format!("response is never passed through {helper}"),
+            ));
+        }
+        for (offset, access) in raw_response_accesses(&text) {
+            let (line, col) = line_col_from_index(&text, offset);
+            hits.push((
+                rel.clone(),
+                line,
+                col,
+                access,
+                format!("raw response access; use {helper}"),
+            ));
+        }
+    }
+
+    if !helper_found {
+        hits.push((
+            pathdiff(frontend_root, &repo_root.join(&rules.helper_file)),
+            1,
+            1,
+            helper.to_string(),
+            "protocol helper is not defined; see docs/webf_protocol.json5".to_string(),
+        ));
+    }
+
+    for module in sources.registered_modules() {
+        let text = fs::read_to_string(&module.file)
+            .with_context(|| format!("read {}", module.file.display()))?;
+        if text.contains("webf-protocol:disable") {
+            continue;
+        }
+        let rel = pathdiff(frontend_root, &module.file);
+        for (offset, what) in dart_protocol_violations(&text, module.body) {
+            let (line, col) = line_col_from_index(&text, offset);
+            hits.push((rel.clone(), line, col, module.name.clone(), what));
+        }
+    }
+
+    if hits.is_empty() {
+        println!("[webf-protocol] OK");
+        return Ok(true);
+    }
+
+    hits.sort();
+    eprintln!("[webf-protocol] Found {} native responses built or read outside the protocol:", hits.len());
+    for (file, line, col, target, detail) in hits.iter().take(50) {
+        eprintln!("- {file}:{line}:{col}  {target}  ({detail})");
+    }
+    if hits.len() > 50 {
+        eprintln!("...and {} more", hits.len() - 50);
+    }
+    eprintln!("\nResponses are `{{ result }}` or `{{ error: {{ code, message }} }}` (lib/webf/protocol.dart).");
+
+    Ok(false)
+}
+
+/// Raw `'result'`/`'error'` map literals in a module class, and values returned
+/// from `invoke` (or the class methods it delegates to) that are not built by
+/// `returnOk`/`returnErr`/`toJson`.
+fn dart_protocol_violations(text: &str, (open, close): (usize, usize)) -> Vec<(usize, String)> {
+    let raw_key_re = Regex::new(r#"['"](?:result|error)['"]\s*:"#).unwrap();
+    let method_re = Regex::new(r"(?m)^\s*(?:[\w<>?,\s]+\s+)?(\w+)\s*\([^)]*\)\s*(?:async\s*)?\{").unwrap();
+    let return_re = Regex::new(r"\breturn\b([^;]*);").unwrap();
+    let call_re = Regex::new(r"^(?:await\s+)?(\w+)\s*\(").unwrap();
+
+    let mut out = Vec::new();
+    let body = &text[open..close];
+    for m in raw_key_re.find_iter(body) {
+        out.push((open + m.start(), "raw protocol map; use returnOk/returnErr".to_string()));
+    }
+
+    // Method bodies by name, with byte ranges in `text`.
+    let mut methods: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    for caps in method_re.captures_iter(body) {
+        let name = caps[1].to_string();
+        if ["if", "for", "while", "switch", "catch"].contains(&name.as_str()) {
+            continue;
+        }
+        let brace = open + caps.get(0).unwrap().end() - 1;
+        let end = find_matching_brace(text, brace).unwrap_or(close);
+        methods.entry(name).or_insert((brace, end));
+    }
+
+    let mut queue = vec!["invoke".to_string()];
+    let mut seen = BTreeSet::new();
+    while let Some(name) = queue.pop() {
+        if !seen.insert(name.clone()) {
+            continue;
+        }
+        let Some(&(start, end)) = methods.get(&name) else {
+            continue;
+        };
+        let closures = dart_closure_spans(text, start + 1, end);
+        for caps in return_re.captures_iter(&text[start..end]) {
+            let at = start + caps.get(0).unwrap().start();
+            if closures.iter().any(|&(a, b)| (a..b).contains(&at)) {
+                continue;
+            }
+            let expr = caps[1].trim();
+            if PROTOCOL_DART_BUILDERS.iter().any(|b| expr.contains(b)) {
+                continue;
+            }
+            match call_re.captures(expr) {
+                Some(call) if methods.contains_key(&call[1]) => queue.push(call[1].to_string()),
+                _ => out.push((at, format!("`{name}` returns `{expr}`; use returnOk/returnErr/toJson"))),
+            }
+        }
+    }
+
+    out
+}
+
+/// Bodies of function literals (`(x) { ... }`) inside `text[start..end]`, so
+/// their `return`s are not taken for the enclosing method's.
+fn dart_closure_spans(text: &str, start: usize, end: usize) -> Vec<(usize, usize)> {
+    let bytes = text.as_bytes();
+    let mut spans = Vec::new();
+    let mut i = start;
+    while i < end {
+        if bytes[i] != b'{' {
+            i += 1;
+            continue;
+        }
+        let before = text[start..i].trim_end();
+        let before = before.strip_suffix("async").unwrap_or(before).trim_end();
+        let is_closure = before.ends_with(')') && {
+            // Word before the matching `(`: control keywords open blocks, not closures.
+            let mut depth = 0i32;
+            let mut open = None;
+            for (k, c) in before.char_indices().rev() {
+                match c {
+                    ')' => depth += 1,
+                    '(' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            open = Some(k);
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            let head = open.map(|k| before[..k].trim_end()).unwrap_or("");
+            !["if", "for", "while", "switch", "catch"].contains(&&head[trailing_ident_start(head)..])
+        };
+        let close = find_matching_brace(text, i).unwrap_or(end);
+        if is_closure {
+            spans.push((i, close));
+            i = close + 1;
+        } else {
+            i += 1;
+        }
+    }
+    spans
+}
+
+/// Whether a Web API is available in WebF (`true`/`false`, or a note meaning "missing").
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum ApiSupport {
+    Flag(bool),
+    Note(String),
+}
+
+impl ApiSupport {
+    /// `None` if available, otherwise the reason shown with the violation.
+    fn missing_reason(&self) -> Option<&str> {
+        match self {
+            ApiSupport::Flag(true) => None,
+            ApiSupport::Flag(false) => Some("not available in WebF"),
+            ApiSupport::Note(note) => Some(note),
+        }
+    }
+}
+
+/// `docs/web_apis.json5`: globals and members of well-known objects by WebF support.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WebApiList {
+    globals: BTreeMap<String, ApiSupport>,
+    #[serde(default)]
+    members: BTreeMap<String, BTreeMap<String, ApiSupport>>,
+}
+
+impl WebApiList {
+    fn load(repo_root: &Path) -> Result<Self> {
+        let path = repo_root.join("docs").join("web_apis.json5");
+        let raw = fs::read_to_string(&path).with_context(|| format!("read {}", path.display()))?;
+        json5::from_str(&raw).with_context(|| format!("parse {}", path.display()))
+    }
+
+    /// Why `object.member` is unavailable, if it is.
+    fn member_problem(&self, object: &str, member: &str) -> Option<String> {
+        let members = self.members.get(object)?;
+        match members.get(member) {
+            Some(support) => support.missing_reason().map(str::to_string),
+            None => members
+                .get("*")
+                .and_then(ApiSupport::missing_reason)
+                .map(|_| format!("not in the supported `{object}` members list")),
+        }
+    }
+}
+
+/// Objects that expose globals as properties (`window.fetch`).
+const GLOBAL_OBJECTS: &[&str] = &["window", "globalThis", "self"];
+
+/// Keywords that can directly precede an expression.
+const EXPRESSION_PREFIX_WORDS: &[&str] = &[
+    "new", "return", "await", "void", "throw", "in", "of", "case", "delete", "yield", "else", "do",
+    "instanceof",
+];
+
+/// Words after which an identifier is in a TypeScript type position or only
+/// feature-tested, so referencing a missing API there is harmless.
+const NON_RUNTIME_PREFIX_WORDS: &[&str] = &[
+    "typeof", "as", "satisfies", "implements", "extends", "keyof", "interface", "type", "declare",
+];
+
+fn check_web_apis(
+    frontend_root: &Path,
+    repo_root: &Path,
+    scan_files: &ScanFiles,
+    guards: &BrowserGuards,
+) -> Result<bool> {
+    let apis = WebApiList::load(repo_root)?;
+
+    // (file, line, col, api, reason)
+    let mut hits: Vec<(String, usize, usize, String, String)> = Vec::new();
+    // Hits in code excluded by a WebF guard.
+    let mut browser_only: Vec<(String, usize, usize, String, String)> = Vec::new();
+
+    for path in scan_files.with_extensions(&["ts", "tsx", "js", "jsx", "vue"]) {
+        if path.to_string_lossy().ends_with(".d.ts") {
+            continue;
+        }
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("read {}", path.display()))?;
+        if text.contains("webf-web-apis:disable") {
+            continue;
+        }
+
+        let rel = pathdiff(frontend_root, path);
+        let guarded = guards.ranges(path, &text);
+        for (offset, api, reason) in find_unsupported_web_apis(&text, &script_ranges(path, &text), &apis) {
+            let (line, col) = line_col_from_index(&text, offset);
+            if line_text(&text, offset).contains("webf-web-apis:ignore") {
+                continue;
+            }
+            let target = if in_ranges(&guarded, offset) { &mut browser_only } else { &mut hits };
+            target.push((rel.clone(), line, col, api, reason));
+        }
+    }
+
+    if guards.mode == BrowserOnlyMode::Warn {
+        let lines: Vec<String> = browser_only
+            .iter()
+            .map(|(file, line, col, api, reason)| format!("- {file}:{line}:{col}  {api}  ({reason})"))
+            .collect();
+        guards.print_warnings("webf-web-apis", &lines);
+    }
+
+    if hits.is_empty() {
+        println!("[webf-web-apis] OK");
+        return Ok(true);
+    }
+
+    eprintln!("[webf-web-apis] Found {} references to Web APIs WebF does not provide:", hits.len());
+    for (file, line, col, api, reason) in hits.iter().take(50) {
+        eprintln!("- {file}:{line}:{col}  {api}  ({reason})");
+    }
+    if hits.len() > 50 {
+        eprintln!("...and {} more", hits.len() - 50);
+    }
+    eprintln!("\nFeature-test with `typeof X` where a fallback exists, or silence one line with:");
+    eprintln!("  // webf-web-apis:ignore");
+
+    Ok(false)
+}
+
+/// Byte ranges of script code in a source file: the whole file for JS/TS,
+/// `<script>` bodies for Vue SFCs.
+fn script_ranges(path: &Path, text: &str) -> Vec<(usize, usize)> {
+    if path.extension().and_then(|e| e.to_str()) == Some("vue") {
+        scan_vue_sfc(text).script_blocks
+    } else {
+        vec![(0, text.len())]
+    }
+}
+
+fn line_text(text: &str, offset: usize) -> &str {
+    let start = text[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let end = text[offset..].find('\n').map(|i| offset + i).unwrap_or(text.len());
+    &text[start..end]
+}
+
+/// `(offset, api, reason)` for each runtime reference to a missing global or member.
+fn find_unsupported_web_apis(
+    text: &str,
+    ranges: &[(usize, usize)],
+    apis: &WebApiList,
+) -> Vec<(usize, String, String)> {
+    let ident_re = Regex::new(r"[A-Za-z_$][A-Za-z0-9_$]*").unwrap();
+    let mut hits = Vec::new();
+
+    for &(start, end) in ranges.iter() {
+        let code = mask_js_non_code(&text[start..end]);
+        let declared = locally_declared_names(&code);
+
+        for m in ident_re.find_iter(&code) {
+            let name = m.as_str();
+            if declared.contains(name) || !is_global_position(&code, m.start()) {
+                continue;
+            }
+            if !is_runtime_reference(&code, m.start(), m.end()) {
+                continue;
+            }
+
+            if let Some(reason) = apis.globals.get(name).and_then(ApiSupport::missing_reason) {
+                hits.push((start + m.start(), name.to_string(), reason.to_string()));
+                continue;
+            }
+
+            if let Some((member_start, member)) = member_after(&code, m.end()) {
+                if let Some(reason) = apis.member_problem(name, member) {
+                    hits.push((start + member_start, format!("{name}.{member}"), reason));
+                }
+            }
+        }
+    }
+
+    hits
+}
+
+/// An identifier refers to a global if it is not a property access, or is a
+/// property of `window`/`globalThis`/`self` (which are globals themselves).
+fn is_global_position(code: &str, start: usize) -> bool {
+    let before = code[..start].trim_end();
+    let Some(before) = before.strip_suffix('.') else {
+        return true;
+    };
+    let before = before.strip_suffix('?').unwrap_or(before).trim_end();
+    if before.ends_with('.') {
+        // Spread `...x` is not a member access.
+        return before.ends_with("..");
+    }
+    let object_start = trailing_ident_start(before);
+    GLOBAL_OBJECTS.contains(&&before[object_start..]) && is_global_position(code, object_start)
+}
+
+/// Filter out object keys, type positions and `typeof` feature tests.
+fn is_runtime_reference(code: &str, start: usize, end: usize) -> bool {
+    let before = code[..start].trim_end();
+    let after = code[end..].trim_start();
+
+    // `{ fetch: ... }` / `, fetch?: ...`
+    if (after.starts_with(':') || after.starts_with("?:")) && (before.ends_with('{') || before.ends_with(',')) {
+        return false;
+    }
+
+    // `window.fetch` is judged by the word before `window`.
+    let mut head = before;
+    while let Some(rest) = head.strip_suffix('.') {
+        let rest = rest.strip_suffix('?').unwrap_or(rest).trim_end();
+        let word_start = trailing_ident_start(rest);
+        head = rest[..word_start].trim_end();
+    }
+
+    let prev_word_start = trailing_ident_start(head);
+    let prev_word = &head[prev_word_start..];
+    if NON_RUNTIME_PREFIX_WORDS.contains(&prev_word) {
+        return false;
+    }
+
+    // Words between other words are prose (JSX text), not code.
+    let next_word_len = after
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '$'))
+        .unwrap_or(after.len());
+    let next_word = &after[..next_word_len];
+    if (!prev_word.is_empty() && !EXPRESSION_PREFIX_WORDS.contains(&prev_word))
+        || (!next_word.is_empty() && !["in", "instanceof"].contains(&next_word))
+    {
+        return false;
+    }
+
+    // Type annotations and unions: `: ResizeObserver`, `<ResizeObserver>`, `A | B`.
+    // `a || b` and `a && b` are runtime.
+    if head.ends_with(':') && !head.ends_with("::") {
+        return false;
+    }
+    if head.ends_with('<') {
+        return false;
+    }
+    if (head.ends_with('|') && !head.ends_with("||")) || (head.ends_with('&') && !head.ends_with("&&")) {
+        return false;
+    }
+
+    true
+}
+
+/// Byte offset where the identifier at the end of `s` starts (`s.len()` if none).
+fn trailing_ident_start(s: &str) -> usize {
+    s.char_indices()
+        .rev()
+        .take_while(|&(_, c)| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or(s.len())
+}
+
+/// The member name after `obj.` / `obj?.`, with its offset.
+fn member_after(code: &str, end: usize) -> Option<(usize, &str)> {
+    let rest = &code[end..];
+    let trimmed = rest.trim_start();
+    let trimmed = trimmed.strip_prefix('?').unwrap_or(trimmed);
+    let trimmed = trimmed.strip_prefix('.')?.trim_start();
+    let len = trimmed
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '$'))
+        .unwrap_or(trimmed.len());
+    if len == 0 {
+        return None;
+    }
+    let member_start = end + (rest.len() - trimmed.len());
+    Some((member_start, &trimmed[..len]))
+}
+
+/// Names bound by declarations or imports in the file; such names shadow globals.
+fn locally_declared_names(code: &str) -> BTreeSet<String> {
+    let decl_re = Regex::new(r"\b(?:const|let|var|function|class)\s+([A-Za-z_$][A-Za-z0-9_$]*)").unwrap();
+    let import_re = Regex::new(r"(?s)\bimport\s+(?:type\s+)?([^;]*?)\s+from\b").unwrap();
+    let ident_re = Regex::new(r"[A-Za-z_$][A-Za-z0-9_$]*").unwrap();
+
+    let mut names: BTreeSet<String> = decl_re
+        .captures_iter(code)
+        .map(|caps| caps[1].to_string())
+        .collect();
+    for caps in import_re.captures_iter(code) {
+        // `a as b` binds `b`; keep every identifier, `as` itself is harmless.
+        names.extend(ident_re.find_iter(&caps[1]).map(|m| m.as_str().to_string()));
+    }
+    names
+}
+
+/// Copy of JS/TS source with comments and string/template text blanked out
+/// (same byte length, newlines kept) so regex rules only see code. `${...}`
+/// placeholders inside templates stay visible. Quoted strings end at a newline,
+/// which limits the damage of an apostrophe in JSX text to one line.
+fn mask_js_non_code(src: &str) -> String {
+    let bytes = src.as_bytes();
+    let mut out = bytes.to_vec();
+    let blank = |out: &mut Vec<u8>, from: usize, to: usize| {
+        for b in out[from..to.min(bytes.len())].iter_mut() {
+            if *b != b'\n' {
+                *b = b' ';
+            }
+        }
+    };
+
+    // Brace depths at which an open `${` placeholder resumes its template.
+    let mut templates: Vec<i32> = Vec::new();
+    let mut depth = 0i32;
+    let mut i = 0usize;
+
+    // Scan template text from `from`; returns the index after the closing
+    // backtick or after an opening `${`.
+    let scan_template = |out: &mut Vec<u8>, from: usize, depth: &mut i32, templates: &mut Vec<i32>| -> usize {
+        let mut j = from;
+        while j < bytes.len() {
+            match bytes[j] {
+                b'\\' => j += 2,
+                b'`' => {
+                    blank(out, from, j);
+                    return j + 1;
+                }
+                b'$' if bytes.get(j + 1) == Some(&b'{') => {
+                    blank(out, from, j);
+                    *depth += 1;
+                    templates.push(*depth);
+                    return j + 2;
+                }
+                _ => j += 1,
+            }
+        }
+        blank(out, from, bytes.len());
+        bytes.len()
+    };
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                let end = src[i..].find('\n').map(|p| i + p).unwrap_or(bytes.len());
+                blank(&mut out, i, end);
+                i = end;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let end = src[i + 2..].find("*/").map(|p| i + 2 + p + 2).unwrap_or(bytes.len());
+                blank(&mut out, i, end);
+                i = end;
+            }
+            q @ (b'\'' | b'"') => {
+                let mut j = i + 1;
+                while j < bytes.len() && bytes[j] != q && bytes[j] != b'\n' {
+                    if bytes[j] == b'\\' {
+                        j += 1;
+                    }
+                    j += 1;
+                }
+                blank(&mut out, i + 1, j);
+                i = j + 1;
+            }
+            b'`' => i = scan_template(&mut out, i + 1, &mut depth, &mut templates),
+            b'{' => {
+                depth += 1;
+                i += 1;
+            }
+            b'}' => {
+                if templates.last() == Some(&depth) {
+                    templates.pop();
+                    depth -= 1;
+                    i = scan_template(&mut out, i + 1, &mut depth, &mut templates);
+                } else {
+                    depth -= 1;
+                    i += 1;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+
+    String::from_utf8(out).unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned())
+}
+
+#[cfg(test)]
+mod mask_js_tests {
+    use super::mask_js_non_code;
+
+    #[test]
+    fn blanks_comments_and_string_contents() {
+        let src = "fetch('/api'); // navigator.share()\n/* localStorage */ x = \"a\\\"b\";\n";
+        let masked = mask_js_non_code(src);
+        assert_eq!(masked.len(), src.len());
+        assert_eq!(masked, "fetch('    ');                     \n                   x = \"    \";\n");
+    }
+
+    #[test]
+    fn keeps_template_placeholders() {
+        let src = "`a ${ok({ b: `c${d}` })} e`; f";
+        assert_eq!(mask_js_non_code(src), "`  ${ok({ b: ` ${d}` })}  `; f");
+    }
+
+    #[test]
+    fn keeps_multibyte_offsets() {
+        let src = "// 注释\nconst s = '颜色'; navigator";
+        let masked = mask_js_non_code(src);
+        assert_eq!(masked.len(), src.len());
+        assert_eq!(masked.find("navigator"), src.find("navigator"));
+        assert!(masked.starts_with(&" ".repeat("// 注释".len())));
+    }
+}
+
+/// Byte ranges of `code` (masked by `mask_js_non_code`) that a WebF guard
+/// keeps off the device: the body of `if (!guard)` / `if (!guard && ..)`, the
+/// `else` of `if (guard)`, the off-device branch of `guard ? a : b` and the
+/// right side of `!guard && expr`.
+fn guarded_browser_ranges(code: &str, guards: &[String]) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+
+    for guard in guards {
+        let re = Regex::new(&format!(r"(!\s*)?\b{}\b", regex::escape(guard))).unwrap();
+        for caps in re.captures_iter(code) {
+            let whole = caps.get(0).unwrap();
+            let negated = caps.get(1).is_some();
+            let before = code[..whole.start()].trim_end();
+            if before.ends_with('.') {
+                continue;
+            }
+            let after = code[whole.end()..].trim_start();
+            let after_pos = code.len() - after.len();
+
+            if let Some(cond) = before.strip_suffix('(') {
+                let cond = cond.trim_end();
+                if &cond[trailing_ident_start(cond)..] == "if" {
+                    let close = if after.starts_with(')') {
+                        Some(after_pos)
+                    } else if negated && after.starts_with("&&") {
+                        let end = expression_end(code, after_pos + 2);
+                        code[end..].starts_with(')').then_some(end)
+                    } else {
+                        None
+                    };
+                    let Some(close) = close else { continue };
+                    let (body, body_end) = statement_span(code, close + 1);
+                    if negated {
+                        ranges.push(body);
+                    } else if after.starts_with(')') {
+                        let rest = code[body_end..].trim_start();
+                        if let Some(else_body) = rest.strip_prefix("else") {
+                            if !else_body.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_' || c == '$') {
+                                ranges.push(statement_span(code, code.len() - else_body.len()).0);
+                            }
+                        }
+                    }
+                    continue;
+                }
+            }
+
+            if after.starts_with('?') && !after.starts_with("?.") && !after.starts_with("??") {
+                let colon = expression_end(code, after_pos + 1);
+                if code[colon..].starts_with(':') {
+                    ranges.push(if negated {
+                        (after_pos + 1, colon)
+                    } else {
+                        (colon + 1, expression_end(code, colon + 1))
+                    });
+                }
+                continue;
+            }
+
+            if negated && after.starts_with("&&") {
+                ranges.push((after_pos + 2, expression_end(code, after_pos + 2)));
+            }
+        }
+    }
+
+    ranges
+}
+
+/// Span of the statement starting at `from` (a `{}` block or a single
+/// statement) and the index right after it.
+fn statement_span(code: &str, from: usize) -> ((usize, usize), usize) {
+    let start = from + (code[from..].len() - code[from..].trim_start().len());
+    if code[start..].starts_with('{') {
+        let end = find_matching_brace(code, start).map(|i| i + 1).unwrap_or(code.len());
+        return ((start, end), end);
+    }
+    let end = expression_end(code, start);
+    let after = if code[end..].starts_with(';') { end + 1 } else { end };
+    ((start, end), after)
+}
+
+/// Index where the expression starting at `from` ends: a `,`, `;`, unmatched
+/// closer or ternary `:` at depth 0. JSX elements are skipped as a whole so
+/// their text cannot end the expression.
+fn expression_end(code: &str, from: usize) -> usize {
+    let bytes = code.as_bytes();
+    let mut depth = 0i32;
+    let mut ternaries = 0i32;
+    let mut i = from;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'<' if is_jsx_start(code, from, i) => {
+                i = jsx_element_end(code, i);
+                continue;
+            }
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => {
+                if depth == 0 {
+                    return i;
+                }
+                depth -= 1;
+            }
+            b',' | b';' if depth == 0 => return i,
+            b'?' if depth == 0 => match bytes.get(i + 1) {
+                Some(b'.') | Some(b'?') => i += 1,
+                _ => ternaries += 1,
+            },
+            b':' if depth == 0 => {
+                if ternaries == 0 {
+                    return i;
+                }
+                ternaries -= 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    bytes.len()
+}
+
+/// A `<` opens a JSX element when it starts an expression, not a comparison.
+fn is_jsx_start(code: &str, from: usize, at: usize) -> bool {
+    if !code[at + 1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '>') {
+        return false;
+    }
+    let before = code[from..at].trim_end();
+    before.is_empty()
+        || before.ends_with(['(', '?', ':', ',', '=', '{', '[', '&', '|'])
+        || &before[trailing_ident_start(before)..] == "return"
+}
+
+/// Index right after the JSX element (or fragment) opening at `open`.
+fn jsx_element_end(code: &str, open: usize) -> usize {
+    let bytes = code.as_bytes();
+    let mut depth = 0i32;
+    let mut i = open;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'{' => i = find_matching_brace(code, i).unwrap_or(bytes.len()),
+            b'<' if bytes.get(i + 1) == Some(&b'/') => {
+                let close = code[i..].find('>').map(|p| i + p).unwrap_or(bytes.len());
+                depth -= 1;
+                if depth <= 0 {
+                    return (close + 1).min(bytes.len());
+                }
+                i = close;
+            }
+            b'<' if code[i + 1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '>') => {
+                // Scan the tag, skipping `{...}` attribute values.
+                let mut j = i + 1;
+                while j < bytes.len() && bytes[j] != b'>' {
+                    if bytes[j] == b'{' {
+                        j = find_matching_brace(code, j).unwrap_or(bytes.len());
+                    }
+                    j += 1;
+                }
+                if j < bytes.len() && bytes[j - 1] == b'/' {
+                    if depth == 0 {
+                        return j + 1;
+                    }
+                } else {
+                    depth += 1;
+                }
+                i = j;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    bytes.len()
+}
+
+// No longer needed: parse_args and print_help (clap handles this)
+
+fn locate_frontend_root(start: &Path) -> Result<PathBuf> {
+    // Typical: run from `frontend/`.
+    if start.join("package.json").is_file() && start.join("scripts").is_dir() {
+        return Ok(start.to_path_buf());
+    }
+
+    // If run from repo root.
+    let candidate = start.join("frontend");
+    if candidate.join("package.json").is_file() && candidate.join("scripts").is_dir() {
+        return Ok(candidate);
+    }
+
+    // Walk up a few levels and try `frontend/`.
+    let mut current = start.to_path_buf();
+    for _ in 0..5 {
+        if current.join("frontend").join("package.json").is_file() {
+            return Ok(current.join("frontend"));
+        }
+        if current.join("package.json").is_file() && current.join("scripts").is_dir() {
+            return Ok(current);
+        }
+        if !current.pop() {
+            break;
+        }
+    }
+
+    Err(anyhow!(
+        "Could not locate frontend root from: {} (expected to find frontend/package.json)",
+        start.display()
+    ))
+}
+
+/// A statically parsed JavaScript literal.
+///
+/// Only the subset used by config modules is understood: objects, arrays,
+/// strings, numbers, booleans and `null`. Anything else (calls, identifiers,
+/// spreads) is kept as raw source text in `Expr`.
+#[derive(Debug, Clone, PartialEq)]
+enum JsValue {
+    Object(Vec<(String, JsValue)>),
+    Array(Vec<JsValue>),
+    Str(String),
+    Num(f64),
+    Bool(bool),
+    Null,
+    Expr(String),
+}
+
+impl JsValue {
+    fn get(&self, key: &str) -> Option<&JsValue> {
+        match self {
+            JsValue::Object(entries) => entries.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            JsValue::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn as_array(&self) -> Option<&[JsValue]> {
+        match self {
+            JsValue::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+/// Parse the value assigned to `module.exports` in a CommonJS module (or the
+/// `export default` of an ES module config).
+///
+/// `module.exports = someIdent` is followed to a top-level
+/// `const|let|var someIdent = ...` declaration in the same file.
+fn parse_commonjs_exports(src: &str) -> Result<JsValue> {
+    let exports_re = Regex::new(r"module\.exports\s*=\s*|export\s+default\s+").unwrap();
+    let m = exports_re
+        .find(src)
+        .ok_or_else(|| anyhow!("no `module.exports = ...` assignment found"))?;
+
+    let mut parser = JsLiteralParser::new(src, m.end());
+    let value = parser.parse_value()?;
+
+    if let JsValue::Expr(expr) = &value {
+        let ident = expr.trim();
+        if ident.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$') {
+            let decl_re = Regex::new(&format!(r"(?m)^\s*(?:const|let|var)\s+{}\s*=\s*", regex::escape(ident))).unwrap();
+            if let Some(decl) = decl_re.find(src) {
+                return JsLiteralParser::new(src, decl.end()).parse_value();
+            }
+        }
+    }
+
+    Ok(value)
+}
+
+struct JsLiteralParser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> JsLiteralParser<'a> {
+    fn new(src: &'a str, pos: usize) -> Self {
+        Self { src, pos }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn skip_trivia(&mut self) {
+        loop {
+            let rest = &self.src[self.pos..];
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if trimmed.starts_with("//") {
+                self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else if trimmed.starts_with("/*") {
+                self.pos += trimmed.find("*/").map(|i| i + 2).unwrap_or(trimmed.len());
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<JsValue> {
+        self.skip_trivia();
+        match self.peek() {
+            Some('{') => self.parse_object(),
+            Some('[') => self.parse_array(),
+            Some(q @ ('\'' | '"' | '`')) => {
+                let start = self.pos;
+                let s = self.parse_string(q)?;
+                // A template with placeholders is not a static value.
+                if q == '`' && s.contains("${") {
+                    return Ok(JsValue::Expr(self.src[start..self.pos].to_string()));
+                }
+                Ok(JsValue::Str(s))
+            }
+            Some(_) => self.parse_scalar_or_expr(),
+            None => Err(anyhow!("unexpected end of input")),
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<JsValue> {
+        self.pos += 1; // `{`
+        let mut entries = Vec::new();
+        loop {
+            self.skip_trivia();
+            match self.peek() {
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(JsValue::Object(entries));
+                }
+                Some(',') => {
+                    self.pos += 1;
+                    continue;
+                }
+                Some('.') if self.src[self.pos..].starts_with("...") => {
+                    let start = self.pos;
+                    self.skip_expr();
+                    entries.push((String::new(), JsValue::Expr(self.src[start..self.pos].to_string())));
+                    continue;
+                }
+                Some(q @ ('\'' | '"')) => {
+                    let key = self.parse_string(q)?;
+                    entries.push((key, self.parse_property_value()?));
+                }
+                Some('[') => {
+                    // Computed key (`[name]: value`): not static, keep the entry opaque.
+                    let start = self.pos;
+                    self.skip_expr();
+                    entries.push((String::new(), JsValue::Expr(self.src[start..self.pos].to_string())));
+                }
+                Some(_) => {
+                    let start = self.pos;
+                    while let Some(c) = self.peek() {
+                        if c.is_alphanumeric() || c == '_' || c == '$' {
+                            self.pos += c.len_utf8();
+                        } else {
+                            break;
+                        }
+                    }
+                    if start == self.pos {
+                        return Err(anyhow!("unsupported object key at offset {}", self.pos));
+                    }
+                    let key = self.src[start..self.pos].to_string();
+                    self.skip_trivia();
+                    if self.peek() == Some(':') {
+                        entries.push((key, self.parse_property_value()?));
+                    } else {
+                        // Shorthand property or method: keep as an expression.
+                        let expr_start = start;
+                        self.skip_expr();
+                        entries.push((key, JsValue::Expr(self.src[expr_start..self.pos].to_string())));
+                    }
+                }
+                None => return Err(anyhow!("unterminated object literal")),
+            }
+        }
+    }
+
+    fn parse_property_value(&mut self) -> Result<JsValue> {
+        self.skip_trivia();
+        if self.peek() != Some(':') {
+            return Err(anyhow!("expected `:` at offset {}", self.pos));
+        }
+        self.pos += 1;
+        self.parse_value()
+    }
+
+    fn parse_array(&mut self) -> Result<JsValue> {
+        self.pos += 1; // `[`
+        let mut items = Vec::new();
+        loop {
+            self.skip_trivia();
+            match self.peek() {
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(JsValue::Array(items));
+                }
+                Some(',') => self.pos += 1,
+                Some(_) => items.push(self.parse_value()?),
+                None => return Err(anyhow!("unterminated array literal")),
+            }
+        }
+    }
+
+    fn parse_string(&mut self, quote: char) -> Result<String> {
+        self.pos += 1;
+        let mut out = String::new();
+        let mut chars = self.src[self.pos..].char_indices();
+        while let Some((i, c)) = chars.next() {
+            if c == '\\' {
+                if let Some((_, esc)) = chars.next() {
+                    out.push(match esc {
+                        'n' => '\n',
+                        't' => '\t',
+                        other => other,
+                    });
+                }
+                continue;
+            }
+            if c == quote {
+                self.pos += i + 1;
+                return Ok(out);
+            }
+            out.push(c);
+        }
+        Err(anyhow!("unterminated string literal"))
+    }
+
+    fn parse_scalar_or_expr(&mut self) -> Result<JsValue> {
+        let start = self.pos;
+        self.skip_expr();
+        let raw = self.src[start..self.pos].trim();
+        Ok(match raw {
+            "true" => JsValue::Bool(true),
+            "false" => JsValue::Bool(false),
+            "null" | "undefined" => JsValue::Null,
+            _ => match raw.parse::<f64>() {
+                Ok(n) => JsValue::Num(n),
+                Err(_) => JsValue::Expr(raw.to_string()),
+            },
+        })
+    }
+
+    /// Advance past an arbitrary expression, stopping at a top-level `,`, `}` or `]`.
+    fn skip_expr(&mut self) {
+        let bytes = self.src.as_bytes();
+        let mut depth = 0i32;
+        while self.pos < bytes.len() {
+            match bytes[self.pos] {
+                q @ (b'\'' | b'"' | b'`') => {
+                    let _ = self.parse_string(q as char);
+                    continue;
+                }
+                b'(' | b'[' | b'{' => depth += 1,
+                b')' | b']' | b'}' if depth > 0 => depth -= 1,
+                b',' | b'}' | b']' | b';' if depth == 0 => return,
+                b'/' if bytes.get(self.pos + 1) == Some(&b'/') || bytes.get(self.pos + 1) == Some(&b'*') => {
+                    self.skip_trivia();
+                    continue;
+                }
+                _ => {}
+            }
+            self.pos += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod js_literal_tests {
+    use super::{parse_commonjs_exports, JsValue, TailwindConfig};
+
+    #[test]
+    fn parses_config_literals() {
+        let src = "// tailwind\nmodule.exports = {\n  content: ['./src/**/*.tsx', \"./index.html\"],\n  \
+                   corePlugins: { preflight: false, /* keep */ 'ring-opacity': true },\n  \
+                   darkMode: 'media', plugins: [require('x')], theme: { extend: {} },\n};\n";
+        let exports = parse_commonjs_exports(src).unwrap();
+        let content: Vec<&str> = exports.get("content").unwrap().as_array().unwrap().iter().filter_map(JsValue::as_str).collect();
+        assert_eq!(content, ["./src/**/*.tsx", "./index.html"]);
+        assert_eq!(exports.get("corePlugins").unwrap().get("preflight"), Some(&JsValue::Bool(false)));
+        assert_eq!(exports.get("corePlugins").unwrap().get("ring-opacity"), Some(&JsValue::Bool(true)));
+        assert_eq!(exports.get("darkMode").and_then(JsValue::as_str), Some("media"));
+        assert_eq!(exports.get("plugins").unwrap().as_array().unwrap(), [JsValue::Expr("require('x')".into())]);
+    }
+
+    #[test]
+    fn keeps_computed_keys_opaque() {
+        let src = "const key = 'dark';\nexport default { [key]: 'class', [`${key}X`]: { a: 1 }, darkMode: 'media' };";
+        let exports = parse_commonjs_exports(src).unwrap();
+        assert_eq!(exports.get("darkMode").and_then(JsValue::as_str), Some("media"));
+        let JsValue::Object(entries) = &exports else {
+            panic!("{exports:?}");
+        };
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0], (String::new(), JsValue::Expr("[key]: 'class'".into())));
+    }
+
+    #[test]
+    fn resolves_exported_identifiers() {
+        let src = "const config = { content: ['./a/**/*.ts'] };\nmodule.exports = config;\n";
+        let exports = parse_commonjs_exports(src).unwrap();
+        assert_eq!(exports.get("content").unwrap().as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn unparseable_config_loads_empty() {
+        let dir = std::env::temp_dir().join(format!("webf-tailwind-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("tailwind.config.js"), "module.exports = { content: ['./src/**/*.ts'], @ };").unwrap();
+        let config = TailwindConfig::load(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(config.unparsed && config.path.is_some() && config.content.is_empty(), "{config:?}");
+    }
+}
+
+/// Config file names probed in a project root, in order.
+const TAILWIND_CONFIG_FILES: &[&str] = &["tailwind.config.cjs", "tailwind.config.js", "tailwind.config.mjs"];
+
+/// The parts of `tailwind.config.cjs` the checker relies on.
+#[derive(Debug, Default)]
+struct TailwindConfig {
+    /// The config file, if the project has one.
+    path: Option<PathBuf>,
+    /// `content` globs, as written in the config (e.g. `./src/**/*.{js,ts}`).
+    content: Vec<String>,
+    /// `corePlugins` entries set to a boolean.
+    core_plugins: BTreeMap<String, bool>,
+    /// `darkMode`, if set.
+    dark_mode: Option<JsValue>,
+    /// `plugins` entries (usually `require(...)` expressions).
+    plugins: Vec<JsValue>,
+    /// The file exists but could not be read or parsed (already reported as a warning).
+    unparsed: bool,
+}
+
+impl TailwindConfig {
+    /// Load the config from the frontend root; a missing file yields an empty config,
+    /// an unparseable one an empty config marked `unparsed` plus a warning.
+    fn load(frontend_root: &Path) -> Self {
+        let Some(path) = TAILWIND_CONFIG_FILES
+            .iter()
+            .map(|name| frontend_root.join(name))
+            .find(|p| p.is_file())
+        else {
+            return Self::default();
+        };
+
+        let exports = fs::read_to_string(&path)
+            .with_context(|| format!("read {}", path.display()))
+            .and_then(|raw| parse_commonjs_exports(&raw).with_context(|| format!("parse {}", path.display())));
+        match exports {
+            Ok(exports) => Self::from_exports(path, &exports),
+            Err(err) => {
+                eprintln!("[webf-tailwind] warning: {err:#}; continuing without the Tailwind config");
+                Self {
+                    path: Some(path),
+                    unparsed: true,
+                    ..Self::default()
+                }
+            }
+        }
+    }
+
+    fn from_exports(path: PathBuf, exports: &JsValue) -> Self {
+
+        let content = match exports.get("content") {
+            Some(JsValue::Array(items)) => items.iter().filter_map(|v| v.as_str().map(str::to_string)).collect(),
+            // `content: { files: [...] }` form.
+            Some(obj @ JsValue::Object(_)) => obj
+                .get("files")
+                .and_then(JsValue::as_array)
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        let mut core_plugins = BTreeMap::new();
+        if let Some(JsValue::Object(entries)) = exports.get("corePlugins") {
+            for (name, value) in entries.iter() {
+                if let JsValue::Bool(enabled) = value {
+                    core_plugins.insert(name.clone(), *enabled);
+                }
+            }
+        }
+
+        let dark_mode = exports.get("darkMode").cloned();
+        let plugins = exports
+            .get("plugins")
+            .and_then(JsValue::as_array)
+            .map(<[JsValue]>::to_vec)
+            .unwrap_or_default();
+
+        Self {
+            path: Some(path),
+            content,
+            core_plugins,
+            dark_mode,
+            plugins,
+            unparsed: false,
+        }
+    }
+
+    fn disabled_core_plugins(&self) -> BTreeSet<String> {
+        self.core_plugins
+            .iter()
+            .filter(|(_, enabled)| !**enabled)
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+}
+
+/// Core plugins that must stay disabled: their output relies on properties
+/// (or custom-property opacity tricks) WebF does not support.
+const REQUIRED_DISABLED_CORE_PLUGINS: &[&str] = &[
+    "preflight",
+    "textOpacity",
+    "backgroundOpacity",
+    "borderOpacity",
+    "divideOpacity",
+    "placeholderOpacity",
+    "ringOpacity",
+];
+
+/// `darkMode` value required by the OpenWebF theming docs.
+const REQUIRED_DARK_MODE: &str = "media";
+
+/// Tailwind plugins known to emit CSS outside the WebF property whitelist.
+const UNSUPPORTED_TAILWIND_PLUGINS: &[(&str, &str)] = &[
+    ("@tailwindcss/forms", "base form resets use appearance and outline-offset"),
+    ("@tailwindcss/typography", "prose styles use quotes, list-style markers and ::marker"),
+    ("@tailwindcss/aspect-ratio", "uses the padding-bottom hack with absolute children"),
+    ("@tailwindcss/container-queries", "emits @container rules and container-type"),
+    ("tailwindcss-animate", "emits @keyframes driven by CSS custom properties"),
+    ("daisyui", "component layer relies on appearance, mask and color-mix()"),
+];
+
+fn check_tailwind_config(frontend_root: &Path, tailwind: &TailwindConfig) -> Result<bool> {
+    let Some(path) = tailwind.path.as_ref() else {
+        let package_json = fs::read_to_string(frontend_root.join("package.json")).unwrap_or_default();
+        if !package_json.contains("\"tailwindcss\"") {
+            println!("[webf-tailwind-config] OK (project does not use Tailwind)");
+            return Ok(true);
+        }
+        eprintln!(
+            "[webf-tailwind-config] Missing {}",
+            frontend_root.join(TAILWIND_CONFIG_FILES[0]).display()
+        );
+        return Ok(false);
+    };
+    if tailwind.unparsed {
+        println!("[webf-tailwind-config] skipped: {} could not be parsed", pathdiff(frontend_root, path));
+        return Ok(true);
+    }
+
+    let mut problems: Vec<String> = Vec::new();
+
+    for plugin in REQUIRED_DISABLED_CORE_PLUGINS.iter() {
+        match tailwind.core_plugins.get(*plugin) {
+            Some(false) => {}
+            Some(true) => problems.push(format!("corePlugins.{plugin} is re-enabled (must be false)")),
+            None => problems.push(format!("corePlugins.{plugin} is missing (Tailwind enables it by default)")),
+        }
+    }
+
+    match tailwind.dark_mode.as_ref() {
+        Some(JsValue::Str(mode)) if mode == REQUIRED_DARK_MODE => {}
+        Some(JsValue::Str(mode)) => {
+            problems.push(format!("darkMode is '{mode}' (must be '{REQUIRED_DARK_MODE}')"))
+        }
+        Some(other) => problems.push(format!(
+            "darkMode is {} (must be '{REQUIRED_DARK_MODE}')",
+            describe_js_value(other)
+        )),
+        None => problems.push(format!("darkMode is not set (must be '{REQUIRED_DARK_MODE}')")),
+    }
+
+    for plugin in tailwind.plugins.iter() {
+        let Some(name) = tailwind_plugin_name(plugin) else {
+            eprintln!(
+                "[webf-tailwind-config] warning: cannot identify plugin {}; review its output manually",
+                describe_js_value(plugin)
+            );
+            continue;
+        };
+        match UNSUPPORTED_TAILWIND_PLUGINS.iter().find(|(known, _)| *known == name) {
+            Some((_, reason)) => problems.push(format!("plugin `{name}` emits unsupported CSS ({reason})")),
+            None => eprintln!(
+                "[webf-tailwind-config] warning: plugin `{name}` is not known to be WebF-safe; \
+                 run `--only css-props` on its output"
+            ),
+        }
+    }
+
+    if problems.is_empty() {
+        println!("[webf-tailwind-config] OK");
+        return Ok(true);
+    }
+
+    eprintln!("[webf-tailwind-config] {} violates WebF guard rails:", pathdiff(frontend_root, path));
+    for problem in problems.iter() {
+        eprintln!("- {problem}");
+    }
+
+    Ok(false)
+}
+
+/// Package name of a `plugins` entry such as `require('@tailwindcss/forms')`
+/// or `require('daisyui')({ ... })`.
+fn tailwind_plugin_name(plugin: &JsValue) -> Option<String> {
+    let require_re = Regex::new(r#"^require\(\s*['"]([^'"]+)['"]\s*\)"#).unwrap();
+    match plugin {
+        JsValue::Expr(expr) => require_re
+            .captures(expr.trim())
+            .map(|caps| caps.get(1).unwrap().as_str().to_string()),
+        JsValue::Str(name) => Some(name.clone()),
+        _ => None,
+    }
+}
+
+fn describe_js_value(value: &JsValue) -> String {
+    match value {
+        JsValue::Object(_) => "an object".to_string(),
+        JsValue::Array(_) => "an array".to_string(),
+        JsValue::Str(s) => format!("'{s}'"),
+        JsValue::Num(n) => n.to_string(),
+        JsValue::Bool(b) => b.to_string(),
+        JsValue::Null => "null".to_string(),
+        JsValue::Expr(expr) => format!("`{}`", expr.trim()),
+    }
+}
+
+/// File extensions at least one source scanner understands.
+const SCANNABLE_EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx", "css", "html", "vue"];
+
+/// Static HTML loaded by WebF outside the Vite build, relative to the repo root.
+/// Generated copies under `assets/gen` are skipped.
+const STATIC_HTML_ASSET_DIRS: &[&str] = &["assets"];
+
+/// Directories scanned when no Tailwind config is available.
+const FALLBACK_SCAN_DIRS: &[&str] = &["src", "public/effects"];
+
+/// The set of source files the source-level checks run over.
+#[derive(Debug, Default)]
+struct ScanFiles {
+    files: Vec<PathBuf>,
+}
+
+impl ScanFiles {
+    fn with_extensions<'a>(&'a self, exts: &'a [&'a str]) -> impl Iterator<Item = &'a PathBuf> + 'a {
+        self.files.iter().filter(move |p| {
+            let ext = p.extension().and_then(|e| e.to_str()).unwrap_or("");
+            exts.contains(&ext)
+        })
+    }
+
+    /// Resolve the scan set.
+    ///
+    /// Explicit `--scan` paths win. Otherwise the Tailwind `content` globs are the
+    /// default, so anything Tailwind generates classes for is also checked. Drift
+    /// between the two (or content paths no scanner can read) is reported as a
+    /// warning rather than failing the run.
+    fn resolve(
+        frontend_root: &Path,
+        repo_root: &Path,
+        tailwind: &TailwindConfig,
+        overrides: &[PathBuf],
+        include_static_assets: bool,
+    ) -> Result<Self> {
+        let content = &tailwind.content;
+        let excluded = content_negations(content)?;
+        let is_excluded = |p: &PathBuf| {
+            p.strip_prefix(frontend_root)
+                .map(|rel| excluded.is_match(rel))
+                .unwrap_or(false)
+        };
+
+        let mut content_matches: Vec<(String, Vec<PathBuf>)> = Vec::new();
+        for pattern in content.iter().filter(|p| !p.starts_with('!')) {
+            let mut matched = expand_content_glob(frontend_root, pattern)?;
+            matched.retain(|p| !is_excluded(p));
+            content_matches.push((pattern.clone(), matched));
+        }
+
+        let mut files: BTreeSet<PathBuf> = if !overrides.is_empty() {
+            let dirs: Vec<PathBuf> = overrides.iter().map(|p| frontend_root.join(p)).collect();
+            walk_files(&dirs).into_iter().collect()
+        } else if !content.is_empty() {
+            let mut files: BTreeSet<PathBuf> =
+                content_matches.iter().flat_map(|(_, files)| files.iter().cloned()).collect();
+            // Tailwind never lists its own input stylesheets in `content`, but their
+            // `@apply` directives and raw declarations end up in the bundle all the same.
+            let bases: BTreeSet<PathBuf> = content
+                .iter()
+                .filter(|p| !p.starts_with('!'))
+                .map(|p| frontend_root.join(content_glob_base(p)))
+                .filter(|p| p.is_dir())
+                .collect();
+            let bases: Vec<PathBuf> = bases.into_iter().collect();
+            files.extend(
+                walk_files(&bases)
+                    .into_iter()
+                    .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("css") && !is_excluded(p)),
+            );
+            files
+        } else {
+            if tailwind.path.is_some() {
+                eprintln!(
+                    "[webf-scan] warning: no Tailwind `content` globs found; falling back to {}",
+                    FALLBACK_SCAN_DIRS.join(", ")
+                );
+            }
+            let dirs: Vec<PathBuf> = FALLBACK_SCAN_DIRS.iter().map(|d| frontend_root.join(d)).collect();
+            walk_files(&dirs).into_iter().collect()
+        };
+
+        for (pattern, matched) in content_matches.iter() {
+            if matched.is_empty() {
+                eprintln!("[webf-scan] warning: tailwind content `{pattern}` matches no files");
+                continue;
+            }
+
+            let skipped: BTreeSet<String> = matched
+                .iter()
+                .filter(|p| !files.contains(*p))
+                .map(|p| pathdiff(frontend_root, p))
+                .collect();
+            if !skipped.is_empty() {
+                eprintln!(
+                    "[webf-scan] warning: tailwind content `{pattern}` is not covered by --scan ({} files, e.g. {})",
+                    skipped.len(),
+                    skipped.iter().next().unwrap()
+                );
+            }
+
+            let unreadable: BTreeSet<&str> = matched
+                .iter()
+                .filter_map(|p| p.extension().and_then(|e| e.to_str()))
+                .filter(|ext| !SCANNABLE_EXTENSIONS.contains(ext))
+                .collect();
+            if !unreadable.is_empty() {
+                let exts: Vec<&str> = unreadable.into_iter().collect();
+                eprintln!(
+                    "[webf-scan] warning: tailwind content `{pattern}` includes files the checker cannot scan yet ({})",
+                    exts.join(", ")
+                );
+            }
+        }
+
+        if !overrides.is_empty() && !content.is_empty() {
+            let covered: BTreeSet<&PathBuf> = content_matches.iter().flat_map(|(_, f)| f.iter()).collect();
+            for dir in overrides.iter() {
+                let abs = frontend_root.join(dir);
+                let outside = files.iter().any(|f| f.starts_with(&abs) && !covered.contains(f) && is_scannable(f));
+                if outside {
+                    eprintln!(
+                        "[webf-scan] warning: --scan {} includes files outside tailwind content; their classes are never generated",
+                        dir.display()
+                    );
+                }
+            }
+        }
+
+        for dir in STATIC_HTML_ASSET_DIRS.iter().filter(|_| include_static_assets) {
+            let dir = repo_root.join(dir);
+            files.extend(walk_files(std::slice::from_ref(&dir)).into_iter().filter(|p| {
+                p.extension().and_then(|e| e.to_str()) == Some("html") && !p.starts_with(dir.join("gen"))
+            }));
+        }
+
+        Ok(Self { files: files.into_iter().collect() })
+    }
+}
+
+fn is_scannable(path: &Path) -> bool {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    SCANNABLE_EXTENSIONS.contains(&ext)
+}
+
+/// Negated content globs (`!./src/**/*.test.tsx`), matched against paths relative
+/// to the frontend root and subtracted from what the positive globs expand to.
+fn content_negations(content: &[String]) -> Result<globset::GlobSet> {
+    let mut builder = globset::GlobSetBuilder::new();
+    for pattern in content.iter().filter_map(|p| p.strip_prefix('!')) {
+        let pattern = pattern.trim_start_matches("./");
+        builder.add(
+            globset::GlobBuilder::new(pattern)
+                .literal_separator(true)
+                .build()
+                .with_context(|| format!("invalid tailwind content glob `!{pattern}`"))?,
+        );
+    }
+    builder.build().context("build negated tailwind content globs")
+}
+
+/// Expand one Tailwind `content` glob relative to the frontend root.
+fn expand_content_glob(frontend_root: &Path, pattern: &str) -> Result<Vec<PathBuf>> {
+    let pattern = pattern.trim_start_matches("./");
+    let base = frontend_root.join(content_glob_base(pattern));
+
+    let matcher = globset::GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .with_context(|| format!("invalid tailwind content glob `{pattern}`"))?
+        .compile_matcher();
+
+    if base.is_file() {
+        return Ok(vec![base]);
+    }
+
+    Ok(walk_files(&[base])
+        .into_iter()
+        .filter(|p| {
+            p.strip_prefix(frontend_root)
+                .map(|rel| matcher.is_match(rel))
+                .unwrap_or(false)
+        })
+        .collect())
+}
+
+/// The longest literal directory prefix of a content glob (`./src/**/*.tsx` -> `src`).
+fn content_glob_base(pattern: &str) -> PathBuf {
+    let pattern = pattern.trim_start_matches("./");
+    let mut base = PathBuf::new();
+    for part in pattern.split('/') {
+        if part.contains(['*', '?', '[', '{']) {
+            break;
+        }
+        base.push(part);
+    }
+    base
+}
+
+#[cfg(test)]
+mod scan_files_tests {
+    use super::{ScanFiles, TailwindConfig};
+    use std::fs;
+
+    #[test]
+    fn subtracts_negated_content_globs() {
+        let root = std::env::temp_dir().join(format!("webf-scan-{}", std::process::id()));
+        fs::create_dir_all(root.join("src/nested")).unwrap();
+        for file in ["src/app.tsx", "src/app.test.tsx", "src/nested/view.test.tsx", "src/index.css", "src/skip.css"] {
+            fs::write(root.join(file), "").unwrap();
+        }
+        let tailwind = TailwindConfig {
+            content: ["./src/**/*.{tsx,css}", "!./src/**/*.test.tsx", "!src/skip.css"].map(String::from).to_vec(),
+            ..TailwindConfig::default()
+        };
+        let scan = ScanFiles::resolve(&root, &root, &tailwind, &[], false);
+        let mut files: Vec<String> = scan
+            .map(|scan| scan.files.iter().map(|p| super::pathdiff(&root, p)).collect())
+            .unwrap_or_default();
+        fs::remove_dir_all(&root).unwrap();
+        files.sort();
+        assert_eq!(files, ["src/app.tsx", "src/index.css"]);
+    }
+}
+
+fn walk_files(dirs: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for dir in dirs.iter() {
+        if !dir.exists() {
+            continue;
+        }
+        for entry in WalkDir::new(dir)
+            .follow_links(false)
+            .into_iter()
+            .filter_entry(|e| {
+                let name = e.file_name().to_string_lossy();
+                !(e.file_type().is_dir() && (name == "node_modules" || name == "dist"))
+            })
+            .filter_map(|e| e.ok())
+        {
+            if entry.file_type().is_file() {
+                files.push(entry.path().to_path_buf());
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+fn tailwind_blacklist_rules() -> Vec<(&'static str, Regex)> {
+    vec![
+        ("group-hover variant", Regex::new(r"\bgroup-hover:").unwrap()),
+        (
+            "hover transform (scale)",
+            Regex::new(r#"\bhover:scale-[^\s"']+"#).unwrap(),
+        ),
+        (
+            "hover transform (translate)",
+            Regex::new(r#"\bhover:-?translate-[^\s"']+"#).unwrap(),
+        ),
+        (
+            "hover transform (rotate)",
+            Regex::new(r#"\bhover:rotate-[^\s"']+"#).unwrap(),
+        ),
+        (
+            "hover transform (skew)",
+            Regex::new(r#"\bhover:skew-[^\s"']+"#).unwrap(),
+        ),
+        ("transition-all utility", Regex::new(r"\btransition-all\b").unwrap()),
+    ]
+}
+
+fn explicit_project_root(cwd: &Path, root: &Path) -> Result<PathBuf> {
+    let root = cwd.join(root);
+    if !root.join("package.json").is_file() {
+        return Err(anyhow!(
+            "--root {} is not a project root (expected package.json)",
+            root.display()
+        ));
+    }
+    Ok(root)
+}
+
+/// The repo root holds `docs/css_properties.json5`; projects may be nested
+/// (e.g. `contrib/webf_usecases/vue_usecases`), so walk up to find it.
+fn locate_repo_root(project_root: &Path) -> Result<PathBuf> {
+    let mut current = project_root.to_path_buf();
+    loop {
+        if current.join("docs").join("css_properties.json5").is_file() {
+            return Ok(current);
+        }
+        if !current.pop() {
+            break;
+        }
+    }
+
+    project_root
+        .parent()
+        .map(Path::to_path_buf)
+        .ok_or_else(|| anyhow!("frontend root has no parent: {}", project_root.display()))
+}
+
+fn check_tailwind_blacklist(frontend_root: &Path, scan_files: &ScanFiles, guards: &BrowserGuards) -> Result<bool> {
+    let forbidden = tailwind_blacklist_rules();
+
+    let mut hits: Vec<(String, usize, usize, String, String)> = Vec::new();
+    // Hits in code excluded by a WebF guard.
+    let mut browser_only: Vec<(String, usize, usize, String, String)> = Vec::new();
+
+    for path in scan_files.with_extensions(&["ts", "tsx", "js", "jsx"]) {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("read {}", path.display()))?;
+
+        // Opt-out per-file when needed (keep rare)
+        if text.contains("webf-tailwind-blacklist:disable") {
+            continue;
+        }
+
+        let guarded = guards.ranges(path, &text);
+        for (name, re) in forbidden.iter() {
+            for m in re.find_iter(&text) {
+                let (line, col) = line_col_from_index(&text, m.start());
+                let rel = pathdiff(frontend_root, path);
+                let target = if in_ranges(&guarded, m.start()) { &mut browser_only } else { &mut hits };
+                target.push((rel, line, col, name.to_string(), m.as_str().to_string()));
+                if hits.len() > 200 {
+                    break;
+                }
+            }
+        }
+    }
+
+    // Stylesheets: utilities pulled in through `@apply`, plus hand-written
+    // selectors that spell out a Tailwind class (e.g. `.hover\:scale-105`).
+    for path in scan_files.with_extensions(&["css"]) {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("read {}", path.display()))?;
+
+        if text.contains("webf-tailwind-blacklist:disable") {
+            continue;
+        }
+
+        let items = scan_css_source(&text);
+        let mut candidates: Vec<(usize, String)> = items.apply_tokens;
+        for (offset, selector) in items.selectors.iter() {
+            let mut classes = BTreeSet::new();
+            collect_classes_from_selector(selector, &mut classes);
+            candidates.extend(classes.into_iter().map(|class| (*offset, class)));
+        }
+
+        let rel = pathdiff(frontend_root, path);
+        for (offset, token) in candidates.iter() {
+            for (name, re) in forbidden.iter() {
+                if let Some(m) = re.find(token) {
+                    let (line, col) = line_col_from_index(&text, *offset);
+                    hits.push((rel.clone(), line, col, name.to_string(), m.as_str().to_string()));
+                }
+            }
+        }
+    }
+
+    for path in scan_files.with_extensions(&["html", "vue"]) {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("read {}", path.display()))?;
+
+        if text.contains("webf-tailwind-blacklist:disable") {
+            continue;
+        }
+
+        let guarded = guards.ranges(path, &text);
+        let (items, script_blocks) = scan_markup_file(path, &text);
+        let mut candidates: Vec<(usize, String)> = items.class_tokens;
+        for (start, end) in script_blocks {
+            for (_, re) in forbidden.iter() {
+                candidates.extend(re.find_iter(&text[start..end]).map(|m| (start + m.start(), m.as_str().to_string())));
+            }
+        }
+        candidates.extend(items.style_blocks.apply_tokens);
+        for (offset, selector) in items.style_blocks.selectors.iter() {
+            let mut classes = BTreeSet::new();
+            collect_classes_from_selector(selector, &mut classes);
+            candidates.extend(classes.into_iter().map(|class| (*offset, class)));
+        }
+
+        let rel = pathdiff(frontend_root, path);
+        for (offset, token) in candidates.iter() {
+            for (name, re) in forbidden.iter() {
+                if let Some(m) = re.find(token) {
+                    let (line, col) = line_col_from_index(&text, *offset);
+                    let target = if in_ranges(&guarded, *offset) { &mut browser_only } else { &mut hits };
+                    target.push((rel.clone(), line, col, name.to_string(), m.as_str().to_string()));
+                }
+            }
+        }
+    }
+
+    if guards.mode == BrowserOnlyMode::Warn {
+        let lines: Vec<String> = browser_only
+            .iter()
+            .map(|(file, line, col, name, snippet)| format!("- {file}:{line}:{col}  {name}  ({snippet})"))
+            .collect();
+        guards.print_warnings("webf-tailwind-blacklist", &lines);
+    }
+
+    if hits.is_empty() {
+        println!("[webf-tailwind-blacklist] OK");
+        return Ok(true);
+    }
+
+    eprintln!("[webf-tailwind-blacklist] Found forbidden Tailwind patterns:");
+    for (file, line, col, name, snippet) in hits.iter().take(50) {
+        eprintln!("- {file}:{line}:{col}  {name}  ({snippet})");
+    }
+    if hits.len() > 50 {
+        eprintln!("...and {} more", hits.len() - 50);
+    }
+    eprintln!("\nIf you really need to bypass for a file, add:");
+    eprintln!("  // webf-tailwind-blacklist:disable   (or /* ... */ in CSS)");
+
+    Ok(false)
+}
+
+
+/// Tailwind core plugins our config may disable, mapped to the utility prefixes
+/// they own. A prefix matches the utility itself (`shadow`) or any `prefix-*`.
+const TAILWIND_CORE_PLUGIN_PREFIXES: &[(&str, &[&str])] = &[
+    ("textOpacity", &["text-opacity"]),
+    ("backgroundOpacity", &["bg-opacity"]),
+    ("borderOpacity", &["border-opacity"]),
+    ("divideOpacity", &["divide-opacity"]),
+    ("placeholderOpacity", &["placeholder-opacity"]),
+    ("ringOpacity", &["ring-opacity"]),
+    ("opacity", &["opacity"]),
+    ("boxShadow", &["shadow"]),
+    ("boxShadowColor", &["shadow"]),
+    ("ringWidth", &["ring"]),
+    ("ringColor", &["ring"]),
+    ("ringOffsetWidth", &["ring-offset"]),
+    ("ringOffsetColor", &["ring-offset"]),
+    ("transitionProperty", &["transition"]),
+    ("transitionDuration", &["duration"]),
+    ("transitionTimingFunction", &["ease"]),
+    ("transitionDelay", &["delay"]),
+    ("animation", &["animate"]),
+    ("blur", &["blur"]),
+    ("backdropBlur", &["backdrop-blur"]),
+    ("backdropFilter", &["backdrop-filter"]),
+    ("filter", &["filter"]),
+    ("divideWidth", &["divide-x", "divide-y"]),
+    ("divideColor", &["divide"]),
+    ("space", &["space-x", "space-y"]),
+    ("container", &["container"]),
+];
+
+/// Class names that are markers for variants and never emit CSS themselves.
+const TAILWIND_MARKER_CLASSES: &[&str] = &["group", "peer", "dark"];
+
+fn check_tailwind_dead_classes(
+    frontend_root: &Path,
+    dist_dir: &Path,
+    tailwind: &TailwindConfig,
+    scan_files: &ScanFiles,
+) -> Result<bool> {
+    let css_files = collect_dist_files(dist_dir, &["css"]);
+    if css_files.is_empty() {
+        println!(
+            "[webf-dead-classes] skipped: no CSS in {} (build first)",
+            pathdiff(frontend_root, dist_dir)
+        );
+        return Ok(true);
+    }
+
+    let mut emitted: BTreeSet<String> = BTreeSet::new();
+    for css_file in css_files.iter() {
+        let css = fs::read_to_string(css_file)
+            .with_context(|| format!("read {}", css_file.display()))?;
+        emitted.extend(extract_selector_classes(&css));
+    }
+
+    let disabled_plugins = tailwind.disabled_core_plugins();
+
+    // (file, line, col, token, reason)
+    let mut hits: Vec<(String, usize, usize, String, String)> = Vec::new();
+
+    for path in scan_files.with_extensions(&["ts", "tsx", "js", "jsx"]) {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("read {}", path.display()))?;
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+        if text.contains("webf-dead-classes:disable") {
+            continue;
+        }
 
-    let cwd = std::env::current_dir().context("get current working directory")?;
-    let frontend_root = locate_frontend_root(&cwd)?;
-    let repo_root = frontend_root
-        .parent()
-        .ok_or_else(|| anyhow!("frontend root has no parent: {}", frontend_root.display()))?
-        .to_path_buf();
+        let rel = pathdiff(frontend_root, path);
+        for (idx, token) in extract_class_tokens(&text) {
+            if is_marker_class(&token) || emitted.contains(&token) {
+                continue;
+            }
 
-    let mut failed = false;
+            let utility = tailwind_utility_of(&token);
+            let reason = match disabled_core_plugin_for(utility, &disabled_plugins) {
+                Some(plugin) => format!("disabled core plugin: {plugin}"),
+                None => "no matching selector in dist CSS".to_string(),
+            };
 
-    if cli.only == OnlyMode::All || cli.only == OnlyMode::Tailwind {
-        if !check_tailwind_blacklist(&frontend_root)? {
-            failed = true;
+            let (line, col) = line_col_from_index(&text, idx);
+            hits.push((rel.clone(), line, col, token, reason));
         }
     }
 
-    if cli.only == OnlyMode::All || cli.only == OnlyMode::CssProps {
-        if !check_css_properties_with_source_flag(&frontend_root, &repo_root, cli.scan_source)? {
-            failed = true;
+    // Only HTML that Tailwind itself scans; static assets style themselves.
+    for path in scan_files
+        .with_extensions(&["html", "vue"])
+        .filter(|p| p.starts_with(frontend_root))
+    {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("read {}", path.display()))?;
+
+        if text.contains("webf-dead-classes:disable") {
+            continue;
+        }
+
+        // Classes defined by the file's own `<style>` (including Vue `scoped`) are live.
+        let (items, _) = scan_markup_file(path, &text);
+        let mut local = BTreeSet::new();
+        for (_, selector) in items.style_blocks.selectors.iter() {
+            collect_classes_from_selector(selector, &mut local);
+        }
+
+        let rel = pathdiff(frontend_root, path);
+        for (idx, token) in items.class_tokens {
+            if is_marker_class(&token) || emitted.contains(&token) || local.contains(&token) {
+                continue;
+            }
+            let reason = match disabled_core_plugin_for(tailwind_utility_of(&token), &disabled_plugins) {
+                Some(plugin) => format!("disabled core plugin: {plugin}"),
+                None => "no matching selector in dist CSS".to_string(),
+            };
+            let (line, col) = line_col_from_index(&text, idx);
+            hits.push((rel.clone(), line, col, token, reason));
         }
     }
 
-    if failed {
-        std::process::exit(1);
+    if hits.is_empty() {
+        println!("[webf-dead-classes] OK");
+        return Ok(true);
     }
 
-    println!("[webf-check] OK");
-    Ok(())
+    eprintln!("[webf-dead-classes] Found {} class tokens that compile to no CSS:", hits.len());
+    for (file, line, col, token, reason) in hits.iter().take(50) {
+        eprintln!("- {file}:{line}:{col}  {token}  ({reason})");
+    }
+    if hits.len() > 50 {
+        eprintln!("...and {} more", hits.len() - 50);
+    }
+    eprintln!("\nIf a file intentionally uses non-Tailwind class names, add:");
+    eprintln!("  // webf-dead-classes:disable");
+
+    Ok(false)
 }
 
+fn is_marker_class(token: &str) -> bool {
+    let base = token.split('/').next().unwrap_or(token);
+    TAILWIND_MARKER_CLASSES.contains(&base)
+}
 
-// No longer needed: parse_args and print_help (clap handles this)
+/// Strip variants (`dark:hover:`), the important modifier and the negative sign
+/// so `dark:!-mt-2` yields `mt-2`. Colons inside `[...]` are part of the utility.
+fn tailwind_utility_of(token: &str) -> &str {
+    let mut depth = 0i32;
+    let mut last_sep = None;
+    for (i, ch) in token.char_indices() {
+        match ch {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            ':' if depth == 0 => last_sep = Some(i),
+            _ => {}
+        }
+    }
+    let utility = match last_sep {
+        Some(i) => &token[i + 1..],
+        None => token,
+    };
+    let utility = utility.strip_prefix('!').unwrap_or(utility);
+    utility.strip_prefix('-').unwrap_or(utility)
+}
 
-fn locate_frontend_root(start: &Path) -> Result<PathBuf> {
-    // Typical: run from `frontend/`.
-    if start.join("package.json").is_file() && start.join("scripts").is_dir() {
-        return Ok(start.to_path_buf());
+fn disabled_core_plugin_for<'a>(utility: &str, disabled: &'a BTreeSet<String>) -> Option<&'a str> {
+    // Prefer the most specific prefix so `bg-opacity-50` is attributed to
+    // backgroundOpacity rather than a broader plugin.
+    let mut best: Option<(&'a str, usize)> = None;
+    for plugin in disabled.iter() {
+        let Some((_, prefixes)) = TAILWIND_CORE_PLUGIN_PREFIXES
+            .iter()
+            .find(|(name, _)| *name == plugin.as_str())
+        else {
+            continue;
+        };
+        for prefix in prefixes.iter() {
+            let matches = utility == *prefix
+                || utility
+                    .strip_prefix(prefix)
+                    .is_some_and(|rest| rest.starts_with('-'));
+            if matches && best.is_none_or(|(_, len)| prefix.len() > len) {
+                best = Some((plugin.as_str(), prefix.len()));
+            }
+        }
     }
+    best.map(|(plugin, _)| plugin)
+}
 
-    // If run from repo root.
-    let candidate = start.join("frontend");
-    if candidate.join("package.json").is_file() && candidate.join("scripts").is_dir() {
-        return Ok(candidate);
+/// Collect class tokens from `className`/`class` attributes.
+///
+/// String attributes are taken verbatim. Expression attributes (`className={...}`)
+/// contribute every static string literal inside the expression; template-literal
+/// tokens touching a `${...}` placeholder are skipped because their final value
+/// is only known at runtime.
+fn extract_class_tokens(text: &str) -> Vec<(usize, String)> {
+    let attr_re = Regex::new(r"\b(?:className|class)\s*=\s*").unwrap();
+    let mut tokens = Vec::new();
+
+    for m in attr_re.find_iter(text) {
+        let start = m.end();
+        let Some(first) = text[start..].chars().next() else {
+            continue;
+        };
+
+        match first {
+            '"' | '\'' => {
+                if let Some(len) = text[start + 1..].find(first) {
+                    push_class_tokens(text, start + 1, start + 1 + len, &mut tokens);
+                }
+            }
+            '{' => {
+                let Some(end) = find_matching_brace(text, start) else {
+                    continue;
+                };
+                for (lit_start, lit_end) in class_expression_spans(text, start + 1, end) {
+                    push_class_tokens(text, lit_start, lit_end, &mut tokens);
+                }
+            }
+            _ => {}
+        }
     }
 
-    // Walk up a few levels and try `frontend/`.
-    let mut current = start.to_path_buf();
-    for _ in 0..5 {
-        if current.join("frontend").join("package.json").is_file() {
-            return Ok(current.join("frontend"));
+    tokens
+}
+
+/// Callees whose arguments are class values: `cn('a', on && 'b', { c: on })`.
+const CLASS_NAME_HELPERS: &[&str] = &["cn", "clsx", "classNames", "classnames", "twMerge", "twJoin"];
+
+/// String spans of a class expression (`text[start..end]`, e.g. the inside of
+/// `className={...}`) that end up in the class list. Only literals in value
+/// position count: ternary branches, the right side of `&&`, array elements,
+/// `cn`/`clsx` arguments. Comparison operands (`variant === 'primary'`) do not.
+/// Falls back to every string literal when the expression does not parse.
+fn class_expression_spans(text: &str, start: usize, end: usize) -> Vec<(usize, usize)> {
+    let allocator = Allocator::default();
+    match JsParser::new(&allocator, &text[start..end], SourceType::tsx()).parse_expression() {
+        Ok(expr) => {
+            let mut spans = Vec::new();
+            collect_class_values(text, start, &expr, &mut spans);
+            spans.sort_unstable();
+            spans
         }
-        if current.join("package.json").is_file() && current.join("scripts").is_dir() {
-            return Ok(current);
+        Err(_) => string_literal_spans(text, start, end),
+    }
+}
+
+fn collect_class_values(text: &str, base: usize, expr: &Expression, spans: &mut Vec<(usize, usize)>) {
+    let range = |span: oxc_span::Span| (base + span.start as usize, base + span.end as usize);
+    match expr {
+        Expression::StringLiteral(lit) => {
+            let (start, end) = range(lit.span);
+            spans.push((start + 1, end - 1));
         }
-        if !current.pop() {
-            break;
+        Expression::TemplateLiteral(tpl) => {
+            let last = tpl.quasis.len().saturating_sub(1);
+            for (i, quasi) in tpl.quasis.iter().enumerate() {
+                let (start, end) = range(quasi.span);
+                push_template_segment(text, start, end, i > 0, i < last, spans);
+            }
+            for e in tpl.expressions.iter() {
+                collect_class_values(text, base, e, spans);
+            }
+        }
+        Expression::ConditionalExpression(c) => {
+            collect_class_values(text, base, &c.consequent, spans);
+            collect_class_values(text, base, &c.alternate, spans);
+        }
+        Expression::LogicalExpression(l) => {
+            // `on && 'a'`: the left side is only a condition.
+            if l.operator != LogicalOperator::And {
+                collect_class_values(text, base, &l.left, spans);
+            }
+            collect_class_values(text, base, &l.right, spans);
+        }
+        Expression::BinaryExpression(b) if b.operator == BinaryOperator::Addition => {
+            collect_class_values(text, base, &b.left, spans);
+            collect_class_values(text, base, &b.right, spans);
+        }
+        Expression::ParenthesizedExpression(p) => collect_class_values(text, base, &p.expression, spans),
+        Expression::TSAsExpression(e) => collect_class_values(text, base, &e.expression, spans),
+        Expression::TSSatisfiesExpression(e) => collect_class_values(text, base, &e.expression, spans),
+        Expression::ArrayExpression(array) => {
+            for element in array.elements.iter() {
+                match element {
+                    ArrayExpressionElement::SpreadElement(spread) => {
+                        collect_class_values(text, base, &spread.argument, spans)
+                    }
+                    element => {
+                        if let Some(e) = element.as_expression() {
+                            collect_class_values(text, base, e, spans);
+                        }
+                    }
+                }
+            }
+        }
+        Expression::CallExpression(call) if call.callee_name().is_some_and(|n| CLASS_NAME_HELPERS.contains(&n)) => {
+            for arg in call.arguments.iter() {
+                match arg.as_expression() {
+                    // `clsx({ 'text-sm': small, active: on })`: the keys are the classes.
+                    Some(Expression::ObjectExpression(object)) => {
+                        for property in object.properties.iter() {
+                            let ObjectPropertyKind::ObjectProperty(property) = property else {
+                                continue;
+                            };
+                            match &property.key {
+                                PropertyKey::StringLiteral(lit) => {
+                                    let (start, end) = range(lit.span);
+                                    spans.push((start + 1, end - 1));
+                                }
+                                PropertyKey::StaticIdentifier(id) => spans.push(range(id.span)),
+                                _ => {}
+                            }
+                        }
+                    }
+                    Some(e) => collect_class_values(text, base, e, spans),
+                    None => {}
+                }
+            }
         }
+        _ => {}
     }
+}
 
-    Err(anyhow!(
-        "Could not locate frontend root from: {} (expected to find frontend/package.json)",
-        start.display()
-    ))
+fn push_class_tokens(text: &str, start: usize, end: usize, out: &mut Vec<(usize, String)>) {
+    let slice = &text[start..end];
+    let mut offset = 0usize;
+    for raw in slice.split_whitespace() {
+        let pos = offset + slice[offset..].find(raw).unwrap();
+        offset = pos + raw.len();
+        if !raw.chars().any(|c| c.is_ascii_alphabetic()) {
+            continue;
+        }
+        out.push((start + pos, raw.to_string()));
+    }
 }
 
-fn check_tailwind_blacklist(frontend_root: &Path) -> Result<bool> {
-    let target_dirs = [frontend_root.join("src"), frontend_root.join("public").join("effects")];
+/// Given the index of an opening `{`, return the index of its matching `}`,
+/// skipping braces inside quoted strings and template literals.
+fn find_matching_brace(text: &str, open: usize) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut depth = 0i32;
+    let mut quote: Option<u8> = None;
+    let mut escape = false;
 
-    let forbidden: Vec<(&str, Regex)> = vec![
-        ("group-hover variant", Regex::new(r"\bgroup-hover:").unwrap()),
-        (
-            "hover transform (scale)",
-            Regex::new(r#"\bhover:scale-[^\s"']+"#).unwrap(),
-        ),
-        (
-            "hover transform (translate)",
-            Regex::new(r#"\bhover:-?translate-[^\s"']+"#).unwrap(),
-        ),
-        (
-            "hover transform (rotate)",
-            Regex::new(r#"\bhover:rotate-[^\s"']+"#).unwrap(),
-        ),
-        (
-            "hover transform (skew)",
-            Regex::new(r#"\bhover:skew-[^\s"']+"#).unwrap(),
-        ),
-        ("transition-all utility", Regex::new(r"\btransition-all\b").unwrap()),
-    ];
+    let mut i = open;
+    while i < bytes.len() {
+        let c = bytes[i];
+        if escape {
+            escape = false;
+        } else if c == b'\\' {
+            escape = true;
+        } else if let Some(q) = quote {
+            if c == q {
+                quote = None;
+            }
+        } else {
+            match c {
+                b'\'' | b'"' | b'`' => quote = Some(c),
+                b'{' => depth += 1,
+                b'}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(i);
+                    }
+                }
+                _ => {}
+            }
+        }
+        i += 1;
+    }
 
-    let mut hits: Vec<(String, usize, usize, String, String)> = Vec::new();
+    None
+}
 
-    for dir in target_dirs.iter() {
-        if !dir.exists() {
-            continue;
-        }
+/// Spans of the static text of string literals within `text[start..end]`.
+/// Template literals are split around `${...}` and any class token touching a
+/// placeholder is trimmed away.
+fn string_literal_spans(text: &str, start: usize, end: usize) -> Vec<(usize, usize)> {
+    let bytes = text.as_bytes();
+    let mut spans = Vec::new();
+    let mut i = start;
 
-        for entry in WalkDir::new(dir)
-            .follow_links(false)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            if entry.file_type().is_dir() {
-                let name = entry.file_name().to_string_lossy();
-                if name == "node_modules" || name == "dist" {
-                    continue;
+    while i < end {
+        let c = bytes[i];
+        match c {
+            b'\'' | b'"' => {
+                let lit_start = i + 1;
+                let mut j = lit_start;
+                while j < end && bytes[j] != c {
+                    if bytes[j] == b'\\' {
+                        j += 1;
+                    }
+                    j += 1;
                 }
-                continue;
+                spans.push((lit_start, j.min(end)));
+                i = j + 1;
+            }
+            b'`' => {
+                let mut seg_start = i + 1;
+                let mut j = seg_start;
+                let mut after_placeholder = false;
+                while j < end && bytes[j] != b'`' {
+                    if bytes[j] == b'\\' {
+                        j += 2;
+                        continue;
+                    }
+                    if bytes[j] == b'$' && bytes.get(j + 1) == Some(&b'{') {
+                        push_template_segment(text, seg_start, j, after_placeholder, true, &mut spans);
+                        let close = find_matching_brace(text, j + 1).unwrap_or(end);
+                        // Literals inside the placeholder (`${on ? 'a' : 'b'}`) are classes too.
+                        spans.extend(string_literal_spans(text, j + 2, close));
+                        j = close + 1;
+                        seg_start = j;
+                        after_placeholder = true;
+                        continue;
+                    }
+                    j += 1;
+                }
+                push_template_segment(text, seg_start, j.min(end), after_placeholder, false, &mut spans);
+                i = j + 1;
             }
+            _ => i += 1,
+        }
+    }
+
+    spans
+}
 
-            let path = entry.path();
-            let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
-            if !matches!(ext, "ts" | "tsx" | "js" | "jsx") {
+fn push_template_segment(
+    text: &str,
+    mut start: usize,
+    mut end: usize,
+    after_placeholder: bool,
+    before_placeholder: bool,
+    spans: &mut Vec<(usize, usize)>,
+) {
+    let seg = &text[start..end];
+    if after_placeholder {
+        // Drop the partial token glued to the preceding `${...}`.
+        start += seg.find(char::is_whitespace).unwrap_or(seg.len());
+    }
+    if before_placeholder && start < end {
+        let seg = &text[start..end];
+        end = start + seg.rfind(char::is_whitespace).map(|i| i + 1).unwrap_or(0);
+    }
+    if start < end {
+        spans.push((start, end));
+    }
+}
+
+#[cfg(test)]
+mod class_token_tests {
+    use super::extract_class_tokens;
+
+    fn classes(text: &str) -> Vec<String> {
+        extract_class_tokens(text).into_iter().map(|(_, class)| class).collect()
+    }
+
+    #[test]
+    fn skips_comparison_operands() {
+        let text = r#"<div className={variant === 'primary' ? 'bg-blue-500' : 'bg-gray-100'} />"#;
+        assert_eq!(classes(text), ["bg-blue-500", "bg-gray-100"]);
+        let text = r#"<div className={'size' in props && kind !== "ghost" ? "p-2" : ""} />"#;
+        assert_eq!(classes(text), ["p-2"]);
+    }
+
+    #[test]
+    fn takes_values_from_helpers_and_arrays() {
+        let text = r#"<div className={cn('flex', active && 'ring-2', { 'text-sm': small, hidden: !open })} />"#;
+        assert_eq!(classes(text), ["flex", "ring-2", "text-sm", "hidden"]);
+        let text = r#"<div className={['m-1', mode === 'dark' ? 'bg-black' : 'bg-white'].join(' ')} />"#;
+        assert!(classes(text).is_empty(), "unknown calls are not class values");
+        let text = r#"<div className={clsx(['m-1', ...extra], label ?? 'gap-1')} />"#;
+        assert_eq!(classes(text), ["m-1", "gap-1"]);
+    }
+
+    #[test]
+    fn splits_template_literals_around_placeholders() {
+        let text = "<div className={`px-2 text-${tone}-500 ${open ? 'block' : 'hidden'} mt-1`} />";
+        assert_eq!(classes(text), ["px-2", "block", "hidden", "mt-1"]);
+    }
+
+    #[test]
+    fn keeps_plain_attributes() {
+        assert_eq!(classes(r#"<div className="a b" class='c' />"#), ["a", "b", "c"]);
+    }
+}
+
+/// Extract the (unescaped) class names used in selectors of CSS text.
+fn extract_selector_classes(css: &str) -> BTreeSet<String> {
+    let mut classes = BTreeSet::new();
+    let bytes = css.as_bytes();
+    let mut prelude_start = 0usize;
+    let mut depth_in_decl = false;
+    let mut quote: Option<u8> = None;
+
+    let mut i = 0usize;
+    while i < bytes.len() {
+        let c = bytes[i];
+        if let Some(q) = quote {
+            if c == b'\\' {
+                i += 1;
+            } else if c == q {
+                quote = None;
+            }
+            i += 1;
+            continue;
+        }
+        match c {
+            b'\\' => i += 1,
+            b'\'' | b'"' => quote = Some(c),
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let close = css[i + 2..].find("*/").map(|p| i + 2 + p + 2).unwrap_or(bytes.len());
+                i = close;
+                prelude_start = i;
                 continue;
             }
+            b'{' => {
+                let prelude = css[prelude_start..i].trim();
+                // At-rule preludes (`@media ...`) open a block of nested rules; any
+                // other prelude is a selector list opening a declaration block.
+                if !depth_in_decl && !prelude.starts_with('@') {
+                    collect_classes_from_selector(prelude, &mut classes);
+                }
+                depth_in_decl = !prelude.starts_with('@');
+                prelude_start = i + 1;
+            }
+            b'}' => {
+                depth_in_decl = false;
+                prelude_start = i + 1;
+            }
+            b';' if !depth_in_decl => prelude_start = i + 1,
+            _ => {}
+        }
+        i += 1;
+    }
 
-            let text = fs::read_to_string(path)
-                .with_context(|| format!("read {}", path.display()))?;
+    classes
+}
 
-            // Opt-out per-file when needed (keep rare)
-            if text.contains("webf-tailwind-blacklist:disable") {
-                continue;
+fn collect_classes_from_selector(selector: &str, out: &mut BTreeSet<String>) {
+    let chars: Vec<char> = selector.chars().collect();
+    let mut i = 0usize;
+    while i < chars.len() {
+        if chars[i] != '.' {
+            if chars[i] == '\\' {
+                i += 1;
             }
+            i += 1;
+            continue;
+        }
 
-            for (name, re) in forbidden.iter() {
-                for m in re.find_iter(&text) {
-                    let (line, col) = line_col_from_index(&text, m.start());
-                    let rel = pathdiff(&frontend_root, path);
-                    hits.push((rel, line, col, name.to_string(), m.as_str().to_string()));
-                    if hits.len() > 200 {
-                        break;
+        i += 1;
+        let mut name = String::new();
+        while i < chars.len() {
+            let c = chars[i];
+            if c == '\\' {
+                // CSS escape: either a hex code point or a literal character.
+                let mut hex = String::new();
+                let mut j = i + 1;
+                while j < chars.len() && hex.len() < 6 && chars[j].is_ascii_hexdigit() {
+                    hex.push(chars[j]);
+                    j += 1;
+                }
+                if !hex.is_empty() {
+                    if let Some(ch) = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                        name.push(ch);
+                    }
+                    if j < chars.len() && chars[j] == ' ' {
+                        j += 1;
                     }
+                    i = j;
+                } else if let Some(&ch) = chars.get(i + 1) {
+                    name.push(ch);
+                    i += 2;
+                } else {
+                    i += 1;
                 }
+                continue;
+            }
+            if c.is_alphanumeric() || c == '-' || c == '_' || !c.is_ascii() {
+                name.push(c);
+                i += 1;
+                continue;
             }
+            break;
         }
-    }
 
-    if hits.is_empty() {
-        println!("[webf-tailwind-blacklist] OK");
-        return Ok(true);
+        if !name.is_empty() && !name.chars().next().unwrap().is_ascii_digit() {
+            out.insert(name);
+        }
     }
+}
 
-    eprintln!("[webf-tailwind-blacklist] Found forbidden Tailwind patterns:");
-    for (file, line, col, name, snippet) in hits.iter().take(50) {
-        eprintln!("- {file}:{line}:{col}  {name}  ({snippet})");
+/// Files under `dist_dir` with one of `extensions`, sorted.
+fn collect_dist_files(dist_dir: &Path, extensions: &[&str]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if !dist_dir.exists() {
+        return files;
     }
-    if hits.len() > 50 {
-        eprintln!("...and {} more", hits.len() - 50);
+    for entry in WalkDir::new(dist_dir)
+        .follow_links(false)
+        .into_iter()
+        .filter_map(|e| e.ok())
+    {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let p = entry.path();
+        if p.extension().and_then(|e| e.to_str()).is_some_and(|ext| extensions.contains(&ext)) {
+            files.push(p.to_path_buf());
+        }
     }
-    eprintln!("\nIf you really need to bypass for a file, add:");
-    eprintln!("  // webf-tailwind-blacklist:disable");
-
-    Ok(false)
+    files.sort();
+    files
 }
 
-
-fn check_css_properties_with_source_flag(frontend_root: &Path, repo_root: &Path, scan_source: bool) -> Result<bool> {
+fn check_css_properties_with_source_flag(
+    frontend_root: &Path,
+    dist_dir: Option<&Path>,
+    repo_root: &Path,
+    scan_source: bool,
+    scan_files: &ScanFiles,
+    allow_packages: &BTreeMap<String, Vec<String>>,
+    guards: &BrowserGuards,
+) -> Result<bool> {
     let css_properties_path = repo_root.join("docs").join("css_properties.json5");
     if !css_properties_path.is_file() {
         return Err(anyhow!(
@@ -204,7 +5597,19 @@ fn check_css_properties_with_source_flag(frontend_root: &Path, repo_root: &Path,
         ));
     }
 
-    let dist_dir = frontend_root.join("dist");
+    let supported = load_supported_properties(&css_properties_path)?;
+
+    // Source-only projects (shared packages) have no build output of their own.
+    let Some(dist_dir) = dist_dir else {
+        let source_violations = check_css_properties_in_source(frontend_root, &supported, scan_files, guards)?;
+        if source_violations.is_empty() {
+            println!("[check:webf-css-props] OK: No unsupported CSS properties in source (no dist output).");
+            return Ok(true);
+        }
+        print_source_violations(&source_violations);
+        return Ok(false);
+    };
+
     if !dist_dir.exists() {
         eprintln!(
             "[check:webf-css-props] Missing build output at {}. Run `pnpm build` first.",
@@ -213,18 +5618,16 @@ fn check_css_properties_with_source_flag(frontend_root: &Path, repo_root: &Path,
         return Ok(false);
     }
 
-    let supported = load_supported_properties(&css_properties_path)?;
-
     // 1) Source-level check (TSX/JSX): catches inline styles and Tailwind arbitrary properties.
     // This gives actionable file:line pointers without needing to reverse-map Tailwind utilities.
     let source_violations = if scan_source {
-        check_css_properties_in_source(frontend_root, &supported)?
+        check_css_properties_in_source(frontend_root, &supported, scan_files, guards)?
     } else {
         Vec::new()
     };
 
     let mut css_files = Vec::new();
-    for entry in WalkDir::new(&dist_dir)
+    for entry in WalkDir::new(dist_dir)
         .follow_links(false)
         .into_iter()
         .filter_map(|e| e.ok())
@@ -249,35 +5652,52 @@ fn check_css_properties_with_source_flag(frontend_root: &Path, repo_root: &Path,
     }
 
     // 2) Build-output check (dist CSS): authoritative gate for all generated CSS.
-    let mut violations: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    // Each violation is traced to the npm package it came from (source map first,
+    // then the CSS shipped by imported packages) so dependency CSS can be told
+    // apart from ours and accepted per package.
+    let package_css = PackageCssIndex::build(frontend_root, scan_files)?;
+
+    // package -> file -> "prop (normalized: ...) [via]"
+    let mut violations: BTreeMap<String, BTreeMap<String, BTreeSet<String>>> = BTreeMap::new();
+    let mut allowed = 0usize;
+
+    for css_file in css_files {
+        let css = fs::read_to_string(&css_file)
+            .with_context(|| format!("read {}", css_file.display()))?;
+        let source_map = SourceMap::load_for(&css_file, &css);
+        let rel = pathdiff(frontend_root, &css_file);
+
+        for rule in extract_css_rules(&css) {
+            for (offset, prop) in rule.decls.iter() {
+                if prop.starts_with("--") {
+                    continue;
+                }
+                let normalized = normalize_property(prop);
+                if is_supported_css_property(&supported, prop, &normalized) {
+                    continue;
+                }
 
-    for css_file in css_files {
-        let css = fs::read_to_string(&css_file)
-            .with_context(|| format!("read {}", css_file.display()))?;
+                let (package, via) =
+                    attribute_dist_declaration(&css, *offset, &rule.selector, prop, source_map.as_ref(), &package_css);
+                if is_allowed_for_package(allow_packages, &package, prop) {
+                    allowed += 1;
+                    continue;
+                }
 
-        for prop in extract_decl_properties(&css) {
-            if prop.starts_with("--") {
-                continue;
+                violations
+                    .entry(package)
+                    .or_default()
+                    .entry(rel.clone())
+                    .or_default()
+                    .insert(format!("{prop} (normalized: {normalized}) [{}]", via.label()));
             }
-            let normalized = normalize_property(&prop);
-            if supported.contains(&prop)
-                || supported.contains(&normalized)
-                || supported.contains(&format!("-webkit-{normalized}"))
-                || supported.contains(&format!("-moz-{normalized}"))
-                || supported.contains(&format!("-ms-{normalized}"))
-                || supported.contains(&format!("-o-{normalized}"))
-            {
-                continue;
-            }
-
-            let rel = pathdiff(frontend_root, &css_file);
-            violations
-                .entry(rel)
-                .or_default()
-                .insert(format!("{prop} (normalized: {normalized})"));
         }
     }
 
+    if allowed > 0 {
+        println!("[check:webf-css-props] {allowed} dist declarations accepted by per-package allow entries.");
+    }
+
     if violations.is_empty() {
         println!("[check:webf-css-props] OK: All CSS declarations use supported properties (plus custom properties).");
         if !source_violations.is_empty() {
@@ -289,34 +5709,111 @@ fn check_css_properties_with_source_flag(frontend_root: &Path, repo_root: &Path,
         return Ok(true);
     }
 
-    let mut count = 0usize;
-    for (_file, props) in violations.iter() {
-        count += props.len();
-    }
+    let count: usize = violations
+        .values()
+        .flat_map(|files| files.values())
+        .map(|props| props.len())
+        .sum();
 
     if !source_violations.is_empty() {
         print_source_violations(&source_violations);
     }
 
-    eprintln!("[check:webf-css-props] Found {count} unsupported CSS properties in dist CSS (grouped by file):");
+    eprintln!("[check:webf-css-props] Found {count} unsupported CSS properties in dist CSS (grouped by origin package):");
 
     let mut shown = 0usize;
-    for (file, props) in violations.iter() {
-        for prop in props.iter() {
-            eprintln!("- {file} -> {prop}");
-            shown += 1;
-            if shown >= 30 {
-                break;
+    'packages: for (package, files) in violations.iter() {
+        eprintln!("{package}:");
+        for (file, props) in files.iter() {
+            for prop in props.iter() {
+                eprintln!("- {file} -> {prop}");
+                shown += 1;
+                if shown >= 30 {
+                    break 'packages;
+                }
             }
         }
-        if shown >= 30 {
-            break;
-        }
+    }
+    if count > shown {
+        eprintln!("...and {} more", count - shown);
+    }
+
+    if violations.keys().any(|p| p != PROJECT_ORIGIN) {
+        eprintln!("\nTo accept a dependency's CSS, list it under `allow_packages` for this project");
+        eprintln!("in {WORKSPACE_MANIFEST}, e.g. \"allow_packages\": {{ \"@base-ui/react\": [\"cursor\"] }}");
     }
 
     Ok(false)
 }
 
+/// Trace one dist declaration back to the package that shipped it.
+fn attribute_dist_declaration(
+    css: &str,
+    offset: usize,
+    selector: &str,
+    prop: &str,
+    source_map: Option<&SourceMap>,
+    package_css: &PackageCssIndex,
+) -> (String, OriginVia) {
+    if let Some(map) = source_map {
+        let (line, col) = line_col0(css, offset);
+        if let Some((source, _, _)) = map.lookup(line, col) {
+            let package = npm_package_of(source).unwrap_or_else(|| PROJECT_ORIGIN.to_string());
+            return (package, OriginVia::SourceMap);
+        }
+    }
+
+    match package_css.attribute(selector, prop) {
+        Some(package) => (package, OriginVia::PackageCss),
+        None => (PROJECT_ORIGIN.to_string(), OriginVia::Unattributed),
+    }
+}
+
+/// `allow_packages` entries list properties (or `*`) accepted from a package.
+fn is_allowed_for_package(allow_packages: &BTreeMap<String, Vec<String>>, package: &str, prop: &str) -> bool {
+    if package == PROJECT_ORIGIN {
+        return false;
+    }
+    // Ambiguous attributions (`a | b`) are only accepted if every candidate allows it.
+    package.split(" | ").all(|candidate| {
+        allow_packages
+            .get(candidate)
+            .is_some_and(|props| props.iter().any(|p| p == "*" || p == prop))
+    })
+}
+
+#[cfg(test)]
+mod package_attribution_tests {
+    use super::{attribute_dist_declaration, is_allowed_for_package, PackageCssIndex, PROJECT_ORIGIN};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn attributes_only_exact_rules() {
+        let mut index = PackageCssIndex::default();
+        index
+            .by_rule
+            .insert((".toast".to_string(), "backdrop-filter".to_string()), ["sonner".to_string()].into());
+        let css = ".toast{backdrop-filter:blur(4px)}";
+
+        let (package, _) = attribute_dist_declaration(css, 7, ".toast", "backdrop-filter", None, &index);
+        assert_eq!(package, "sonner");
+        // Our own `[backdrop-filter:...]` class is not blamed on the package.
+        let (package, _) = attribute_dist_declaration(css, 7, ".\\[backdrop-filter\\:blur\\]", "backdrop-filter", None, &index);
+        assert_eq!(package, PROJECT_ORIGIN);
+    }
+
+    #[test]
+    fn allow_entries_never_cover_the_project() {
+        let allow = BTreeMap::from([
+            ("sonner".to_string(), vec!["backdrop-filter".to_string()]),
+            (PROJECT_ORIGIN.to_string(), vec!["*".to_string()]),
+        ]);
+        assert!(is_allowed_for_package(&allow, "sonner", "backdrop-filter"));
+        assert!(!is_allowed_for_package(&allow, "sonner", "mask"));
+        assert!(!is_allowed_for_package(&allow, PROJECT_ORIGIN, "backdrop-filter"));
+    }
+}
+
 #[derive(Debug, Clone)]
 struct SourceViolation {
     file: String,
@@ -346,11 +5843,16 @@ fn print_source_violations(violations: &[SourceViolation]) {
 fn check_css_properties_in_source(
     frontend_root: &Path,
     supported: &BTreeSet<String>,
+    scan_files: &ScanFiles,
+    guards: &BrowserGuards,
 ) -> Result<Vec<SourceViolation>> {
-    let target_dirs = [frontend_root.join("src"), frontend_root.join("public").join("effects")];
-
+    // Whole sources: only dash-prefixed properties, so TS index signatures and
+    // computed keys (`[key: string]`) are never mistaken for classes.
     let arbitrary_prop_re = Regex::new(r"\[(--?[A-Za-z][A-Za-z0-9-]*):")
         .expect("compile arbitrary property regex");
+    // Class token lists (className literals, `@apply`): any property.
+    let class_arbitrary_prop_re = Regex::new(r"\[((?:--|-)?[A-Za-z][A-Za-z0-9-]*):")
+        .expect("compile class arbitrary property regex");
 
     // Matches keys at object literal top-level-ish: `{ foo: ... }` or `, foo: ...`.
     // Also supports quoted keys: `{ 'background-color': ... }`.
@@ -361,39 +5863,58 @@ fn check_css_properties_in_source(
             .expect("compile style string key regex");
 
     let mut violations: Vec<SourceViolation> = Vec::new();
+    // Violations in code excluded by a WebF guard.
+    let mut browser_only: Vec<SourceViolation> = Vec::new();
 
-    for dir in target_dirs.iter() {
-        if !dir.exists() {
-            continue;
-        }
+    for path in scan_files.with_extensions(&["tsx", "jsx", "ts", "js"]) {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("read {}", path.display()))?;
 
-        for entry in WalkDir::new(dir)
-            .follow_links(false)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            if entry.file_type().is_dir() {
-                let name = entry.file_name().to_string_lossy();
-                if name == "node_modules" || name == "dist" {
-                    continue;
+        let rel = pathdiff(frontend_root, path);
+        let guarded = guards.ranges(path, &text);
+
+        // Tailwind arbitrary properties: className="[-webkit-mask:none] ..." anywhere,
+        // unprefixed ones (`[mask-type:luminance]`) only inside className literals.
+        let mut candidates: Vec<(usize, String)> = arbitrary_prop_re
+            .captures_iter(&text)
+            .map(|caps| (caps.get(0).unwrap().start(), caps[1].to_string()))
+            .collect();
+        for (offset, token) in extract_class_tokens(&text) {
+            for caps in class_arbitrary_prop_re.captures_iter(&token) {
+                if !caps[1].starts_with('-') {
+                    candidates.push((offset, caps[1].to_string()));
                 }
+            }
+        }
+        candidates.sort();
+        for (idx, prop) in candidates {
+            if prop.starts_with("--") {
                 continue;
             }
-
-            let path = entry.path();
-            let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
-            if !matches!(ext, "tsx" | "jsx" | "ts" | "js") {
+            let normalized = normalize_property(&prop);
+            if is_supported_css_property(supported, &prop, &normalized) {
                 continue;
             }
+            let (line, col) = line_col_from_index(&text, idx);
+            let target = if in_ranges(&guarded, idx) { &mut browser_only } else { &mut violations };
+            target.push(SourceViolation {
+                file: rel.clone(),
+                line,
+                col,
+                prop,
+                normalized,
+                kind: "tailwind-arbitrary".to_string(),
+            });
+        }
 
-            let text = fs::read_to_string(path)
-                .with_context(|| format!("read {}", path.display()))?;
-
-            let rel = pathdiff(frontend_root, path);
+        // Inline React styles: style={{ ... }}
+        for (span_start, span_end) in find_style_object_spans(&text) {
+            let slice = &text[span_start..span_end];
 
-            // Tailwind arbitrary properties: className="[mask-type:luminance] ..."
-            for m in arbitrary_prop_re.captures_iter(&text) {
-                let prop = m.get(1).unwrap().as_str().to_string();
+            // Quoted keys first (can include kebab-case)
+            for caps in style_key_string_re.captures_iter(slice) {
+                let key = caps.get(1).unwrap().as_str();
+                let prop = key.to_string();
                 if prop.starts_with("--") {
                     continue;
                 }
@@ -401,73 +5922,393 @@ fn check_css_properties_in_source(
                 if is_supported_css_property(supported, &prop, &normalized) {
                     continue;
                 }
-                let idx = m.get(0).unwrap().start();
-                let (line, col) = line_col_from_index(&text, idx);
-                violations.push(SourceViolation {
+                let abs_idx = span_start + caps.get(0).unwrap().start();
+                let (line, col) = line_col_from_index(&text, abs_idx);
+                let target = if in_ranges(&guarded, abs_idx) { &mut browser_only } else { &mut violations };
+                target.push(SourceViolation {
+                    file: rel.clone(),
+                    line,
+                    col,
+                    prop,
+                    normalized,
+                    kind: "inline-style".to_string(),
+                });
+            }
+
+            // Identifier keys (camelCase)
+            for caps in style_key_ident_re.captures_iter(slice) {
+                let key = caps.get(1).unwrap().as_str();
+                // Ignore obvious non-style object literals by requiring the attribute context.
+                // This span is already inside style={{...}}, so it's safe to treat as style keys.
+                let (prop, normalized) = css_prop_from_js_key(key);
+                if prop.starts_with("--") {
+                    continue;
+                }
+                if is_supported_css_property(supported, &prop, &normalized) {
+                    continue;
+                }
+                let abs_idx = span_start + caps.get(0).unwrap().start();
+                let (line, col) = line_col_from_index(&text, abs_idx);
+                let target = if in_ranges(&guarded, abs_idx) { &mut browser_only } else { &mut violations };
+                target.push(SourceViolation {
                     file: rel.clone(),
                     line,
                     col,
                     prop,
                     normalized,
-                    kind: "tailwind-arbitrary".to_string(),
+                    kind: "inline-style".to_string(),
                 });
             }
+        }
+    }
 
-            // Inline React styles: style={{ ... }}
-            for (span_start, span_end) in find_style_object_spans(&text) {
-                let slice = &text[span_start..span_end];
+    for path in scan_files.with_extensions(&["css"]) {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("read {}", path.display()))?;
 
-                // Quoted keys first (can include kebab-case)
-                for caps in style_key_string_re.captures_iter(slice) {
-                    let key = caps.get(1).unwrap().as_str();
-                    let prop = key.to_string();
-                    if prop.starts_with("--") {
-                        continue;
+        let rel = pathdiff(frontend_root, path);
+        let items = scan_css_source(&text);
+
+        let mut candidates: Vec<(usize, String, &str)> = Vec::new();
+        for (offset, prop) in items.declarations {
+            candidates.push((offset, prop, "css-declaration"));
+        }
+        // `@apply [mask-type:luminance]` compiles to a raw declaration.
+        for (offset, token) in items.apply_tokens.iter() {
+            for caps in class_arbitrary_prop_re.captures_iter(token) {
+                candidates.push((*offset, caps.get(1).unwrap().as_str().to_string(), "css-apply-arbitrary"));
+            }
+        }
+
+        for (offset, prop, kind) in candidates {
+            if prop.starts_with("--") {
+                continue;
+            }
+            let normalized = normalize_property(&prop);
+            if is_supported_css_property(supported, &prop, &normalized) {
+                continue;
+            }
+            let (line, col) = line_col_from_index(&text, offset);
+            violations.push(SourceViolation {
+                file: rel.clone(),
+                line,
+                col,
+                prop,
+                normalized,
+                kind: kind.to_string(),
+            });
+        }
+    }
+
+    for path in scan_files.with_extensions(&["html", "vue"]) {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("read {}", path.display()))?;
+
+        let rel = pathdiff(frontend_root, path);
+        let guarded = guards.ranges(path, &text);
+        let (items, script_blocks) = scan_markup_file(path, &text);
+        let (block_kind, attr_kind) = if path.extension().and_then(|e| e.to_str()) == Some("vue") {
+            ("vue-style-block", "vue-template-style")
+        } else {
+            ("html-style-block", "html-style-attr")
+        };
+
+        let mut candidates: Vec<(usize, String, &str)> = Vec::new();
+        for (offset, prop) in items.style_blocks.declarations {
+            candidates.push((offset, prop, block_kind));
+        }
+        for (offset, prop) in items.inline_declarations {
+            candidates.push((offset, prop, attr_kind));
+        }
+        for (offset, token) in items.class_tokens.iter().chain(items.style_blocks.apply_tokens.iter()) {
+            for caps in class_arbitrary_prop_re.captures_iter(token) {
+                candidates.push((*offset, caps.get(1).unwrap().as_str().to_string(), "tailwind-arbitrary"));
+            }
+        }
+        for (start, end) in script_blocks {
+            for caps in arbitrary_prop_re.captures_iter(&text[start..end]) {
+                let m = caps.get(1).unwrap();
+                candidates.push((start + m.start(), m.as_str().to_string(), "tailwind-arbitrary"));
+            }
+        }
+
+        for (offset, prop, kind) in candidates {
+            if prop.starts_with("--") {
+                continue;
+            }
+            let normalized = normalize_property(&prop);
+            if is_supported_css_property(supported, &prop, &normalized) {
+                continue;
+            }
+            let (line, col) = line_col_from_index(&text, offset);
+            let target = if in_ranges(&guarded, offset) { &mut browser_only } else { &mut violations };
+            target.push(SourceViolation {
+                file: rel.clone(),
+                line,
+                col,
+                prop,
+                normalized,
+                kind: kind.to_string(),
+            });
+        }
+    }
+
+    if guards.mode == BrowserOnlyMode::Warn {
+        let lines: Vec<String> = browser_only
+            .iter()
+            .map(|v| format!("- {}:{}:{}  {}  {} (normalized: {})", v.file, v.line, v.col, v.kind, v.prop, v.normalized))
+            .collect();
+        guards.print_warnings("check:webf-css-props", &lines);
+    }
+
+    Ok(violations)
+}
+
+/// Items of interest in a hand-written stylesheet, with byte offsets into the source.
+#[derive(Debug, Default)]
+struct CssSourceItems {
+    /// Style rule selector lists (at-rule preludes excluded).
+    selectors: Vec<(usize, String)>,
+    /// Declaration property names, including custom properties.
+    declarations: Vec<(usize, String)>,
+    /// Individual utilities listed in `@apply` directives.
+    apply_tokens: Vec<(usize, String)>,
+}
+
+/// Walk a CSS source file (Tailwind input, not build output).
+///
+/// Statements are split on `;`, `{` and `}` outside strings, comments and
+/// parentheses. Text before `{` is a selector or at-rule prelude; text before
+/// `;` or `}` is a declaration or an at-rule such as `@apply`. Nested rules
+/// (postcss-nesting style) fall out of the same scheme.
+fn scan_css_source(css: &str) -> CssSourceItems {
+    let mut items = CssSourceItems::default();
+    let bytes = css.as_bytes();
+    let mut stmt_start = 0usize;
+    let mut paren_depth = 0i32;
+    let mut quote: Option<u8> = None;
+
+    let mut i = 0usize;
+    while i < bytes.len() {
+        let c = bytes[i];
+        if let Some(q) = quote {
+            if c == b'\\' {
+                i += 1;
+            } else if c == q {
+                quote = None;
+            }
+            i += 1;
+            continue;
+        }
+
+        match c {
+            b'\\' => i += 1,
+            b'\'' | b'"' => quote = Some(c),
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let close = css[i + 2..].find("*/").map(|p| i + 2 + p + 2).unwrap_or(bytes.len());
+                // A comment before a statement is not part of it.
+                if css[stmt_start..i].trim().is_empty() {
+                    stmt_start = close;
+                }
+                i = close;
+                continue;
+            }
+            b'(' => paren_depth += 1,
+            b')' => paren_depth = (paren_depth - 1).max(0),
+            b'{' if paren_depth == 0 => {
+                let (offset, prelude) = trimmed_span(css, stmt_start, i);
+                if !prelude.is_empty() && !prelude.starts_with('@') {
+                    items.selectors.push((offset, prelude.to_string()));
+                }
+                stmt_start = i + 1;
+            }
+            b';' | b'}' if paren_depth == 0 => {
+                let (offset, stmt) = trimmed_span(css, stmt_start, i);
+                if let Some(rest) = stmt.strip_prefix("@apply") {
+                    let rest_offset = offset + "@apply".len();
+                    let mut cursor = 0usize;
+                    for token in rest.split_whitespace() {
+                        let pos = cursor + rest[cursor..].find(token).unwrap();
+                        cursor = pos + token.len();
+                        // `@apply foo !important;`
+                        if token == "!important" {
+                            continue;
+                        }
+                        items.apply_tokens.push((rest_offset + pos, token.to_string()));
                     }
-                    let normalized = normalize_property(&prop);
-                    if is_supported_css_property(supported, &prop, &normalized) {
-                        continue;
+                } else if !stmt.starts_with('@') {
+                    if let Some(colon) = stmt.find(':') {
+                        let prop = stmt[..colon].trim();
+                        if !prop.is_empty()
+                            && prop.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                        {
+                            items.declarations.push((offset, prop.to_string()));
+                        }
                     }
-                    let abs_idx = span_start + caps.get(0).unwrap().start();
-                    let (line, col) = line_col_from_index(&text, abs_idx);
-                    violations.push(SourceViolation {
-                        file: rel.clone(),
-                        line,
-                        col,
-                        prop,
-                        normalized,
-                        kind: "inline-style".to_string(),
-                    });
                 }
+                stmt_start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
 
-                // Identifier keys (camelCase)
-                for caps in style_key_ident_re.captures_iter(slice) {
-                    let key = caps.get(1).unwrap().as_str();
-                    // Ignore obvious non-style object literals by requiring the attribute context.
-                    // This span is already inside style={{...}}, so it's safe to treat as style keys.
-                    let (prop, normalized) = css_prop_from_js_key(key);
-                    if prop.starts_with("--") {
-                        continue;
-                    }
-                    if is_supported_css_property(supported, &prop, &normalized) {
-                        continue;
-                    }
-                    let abs_idx = span_start + caps.get(0).unwrap().start();
-                    let (line, col) = line_col_from_index(&text, abs_idx);
-                    violations.push(SourceViolation {
-                        file: rel.clone(),
-                        line,
-                        col,
-                        prop,
-                        normalized,
-                        kind: "inline-style".to_string(),
-                    });
+    items
+}
+
+/// Items extracted from an HTML document, with byte offsets into the HTML.
+#[derive(Debug, Default)]
+struct HtmlSourceItems {
+    /// Everything found in `<style>` blocks.
+    style_blocks: CssSourceItems,
+    /// Declaration property names from `style="..."` attributes.
+    inline_declarations: Vec<(usize, String)>,
+    /// Tokens from `class="..."` attributes.
+    class_tokens: Vec<(usize, String)>,
+}
+
+fn scan_html_source(html: &str) -> HtmlSourceItems {
+    let mut items = HtmlSourceItems::default();
+
+    let style_re = Regex::new(r"(?is)<style\b[^>]*>(.*?)</style\s*>").unwrap();
+    for caps in style_re.captures_iter(html) {
+        let body = caps.get(1).unwrap();
+        let block = scan_css_source(body.as_str());
+        let shift = |v: Vec<(usize, String)>| v.into_iter().map(|(o, s)| (body.start() + o, s));
+        items.style_blocks.selectors.extend(shift(block.selectors));
+        items.style_blocks.declarations.extend(shift(block.declarations));
+        items.style_blocks.apply_tokens.extend(shift(block.apply_tokens));
+    }
+
+    // Blank out comments, scripts and style bodies (keeping offsets) so that
+    // `class=` inside JS strings or CSS text is not mistaken for an attribute.
+    let mut masked = html.to_string();
+    let opaque_re = Regex::new(r"(?is)<!--.*?-->|<script\b[^>]*>.*?</script\s*>|<style\b[^>]*>.*?</style\s*>").unwrap();
+    for m in opaque_re.find_iter(html) {
+        let blank: String = m.as_str().chars().map(|c| if c == '\n' { '\n' } else { ' ' }).collect();
+        // Multi-byte characters are replaced by single spaces; pad to keep byte offsets.
+        let pad = m.as_str().len() - blank.len();
+        masked.replace_range(m.range(), &(blank + &" ".repeat(pad)));
+    }
+
+    let attr_re = Regex::new(r#"(?is)[\s"'](style|class)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap();
+    for caps in attr_re.captures_iter(&masked) {
+        let value = caps.get(2).or_else(|| caps.get(3)).unwrap();
+        let text = &html[value.range()];
+        if caps.get(1).unwrap().as_str().eq_ignore_ascii_case("style") {
+            // The attribute is a declaration list; a trailing `;` closes the last one.
+            let decls = scan_css_source(&format!("{text};"));
+            items
+                .inline_declarations
+                .extend(decls.declarations.into_iter().map(|(o, p)| (value.start() + o, p)));
+        } else {
+            push_class_tokens(html, value.start(), value.end(), &mut items.class_tokens);
+        }
+    }
+
+    items
+}
+
+/// HTML documents and Vue SFCs share the markup scanners. SFC `<script>` bodies
+/// are returned separately so the JS/TS rules can run over them.
+fn scan_markup_file(path: &Path, text: &str) -> (HtmlSourceItems, Vec<(usize, usize)>) {
+    if path.extension().and_then(|e| e.to_str()) == Some("vue") {
+        let sfc = scan_vue_sfc(text);
+        (sfc.markup, sfc.script_blocks)
+    } else {
+        (scan_html_source(text), Vec::new())
+    }
+}
+
+/// A Vue single-file component split into the parts the checks care about.
+#[derive(Debug, Default)]
+struct VueSfcItems {
+    /// `<style>` blocks plus static `class`/`style` attributes and the keys and
+    /// string literals of `:class`/`:style` bindings in the template.
+    markup: HtmlSourceItems,
+    /// Byte ranges of `<script>` / `<script setup>` bodies.
+    script_blocks: Vec<(usize, usize)>,
+}
+
+fn scan_vue_sfc(sfc: &str) -> VueSfcItems {
+    // Static attributes and `<style>` (scoped or not) are plain HTML as far as
+    // the scanners are concerned.
+    let mut markup = scan_html_source(sfc);
+
+    let script_re = Regex::new(r"(?is)<script\b[^>]*>(.*?)</script\s*>").unwrap();
+    let script_blocks: Vec<(usize, usize)> = script_re
+        .captures_iter(sfc)
+        .map(|caps| {
+            let body = caps.get(1).unwrap();
+            (body.start(), body.end())
+        })
+        .collect();
+
+    let template_start = sfc.find("<template").and_then(|i| sfc[i..].find('>').map(|j| i + j + 1));
+    let template_end = sfc.rfind("</template");
+    let (Some(start), Some(end)) = (template_start, template_end) else {
+        return VueSfcItems { markup, script_blocks };
+    };
+    if start >= end {
+        return VueSfcItems { markup, script_blocks };
+    }
+
+    // `:class="{ active: on, 'text-sm': small }"`, `:class="['a', on ? 'b' : '']"`,
+    // `v-bind:style="{ fontSize: size }"`.
+    let bound_re = Regex::new(r#"(?s)\s(?::|v-bind:)(class|style)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap();
+    for caps in bound_re.captures_iter(&sfc[start..end]) {
+        let value = caps.get(2).or_else(|| caps.get(3)).unwrap();
+        let (v_start, v_end) = (start + value.start(), start + value.end());
+        let is_class = caps.get(1).unwrap().as_str() == "class";
+
+        if is_class {
+            for (lit_start, lit_end) in class_expression_spans(sfc, v_start, v_end) {
+                push_class_tokens(sfc, lit_start, lit_end, &mut markup.class_tokens);
+            }
+        }
+
+        let expr = sfc[v_start..v_end].trim_start();
+        if !expr.starts_with('{') {
+            continue;
+        }
+        for (offset, key, quoted) in js_object_keys(&sfc[v_start..v_end]) {
+            if is_class {
+                // Quoted keys were already taken as string literals.
+                if !quoted {
+                    markup.class_tokens.push((v_start + offset, key));
                 }
+            } else {
+                let prop = if quoted { key } else { css_prop_from_js_key(&key).0 };
+                markup.inline_declarations.push((v_start + offset, prop));
             }
         }
     }
 
-    Ok(violations)
+    VueSfcItems { markup, script_blocks }
+}
+
+/// Top-level-ish keys of an object literal: `(offset, key, quoted)`.
+fn js_object_keys(slice: &str) -> Vec<(usize, String, bool)> {
+    let ident_re = Regex::new(r"(?m)(?:^|[,{]\s*)([A-Za-z_$][A-Za-z0-9_$-]*)\s*:").unwrap();
+    let string_re = Regex::new(r#"(?m)(?:^|[,{]\s*)['"]([^'"]+)['"]\s*:"#).unwrap();
+
+    let mut keys: Vec<(usize, String, bool)> = Vec::new();
+    for caps in string_re.captures_iter(slice) {
+        let key = caps.get(1).unwrap();
+        keys.push((key.start(), key.as_str().to_string(), true));
+    }
+    for caps in ident_re.captures_iter(slice) {
+        let key = caps.get(1).unwrap();
+        keys.push((key.start(), key.as_str().to_string(), false));
+    }
+    keys.sort_by_key(|(offset, _, _)| *offset);
+    keys
+}
+
+fn trimmed_span(text: &str, start: usize, end: usize) -> (usize, &str) {
+    let raw = &text[start..end];
+    let trimmed = raw.trim_start();
+    (start + raw.len() - trimmed.len(), trimmed.trim_end())
 }
 
 fn is_supported_css_property(supported: &BTreeSet<String>, prop: &str, normalized: &str) -> bool {
@@ -660,21 +6501,29 @@ fn line_col_from_index(text: &str, index: usize) -> (usize, usize) {
 }
 
 fn pathdiff(root: &Path, file: &Path) -> String {
+    // Files next to the frontend (e.g. repo `assets/`) are shown as `../...`.
+    if let (Err(_), Some(parent)) = (file.strip_prefix(root), root.parent()) {
+        if let Ok(rel) = file.strip_prefix(parent) {
+            return format!("../{}", rel.to_string_lossy().replace('\\', "/"));
+        }
+    }
     let rel = file.strip_prefix(root).unwrap_or(file);
     rel.to_string_lossy().replace('\\', "/")
 }
 
-/// Extract declaration property names from CSS text.
+/// Extract style rules (selector plus declarations) from CSS text.
 ///
 /// This intentionally uses a lightweight parser (no full CSS parsing).
 /// It scans each innermost `{ ... }` block and then extracts `prop:` tokens,
 /// skipping strings and nested parentheses so `data:` in URLs won't be mistaken
 /// as a property.
-fn extract_decl_properties(css: &str) -> BTreeSet<String> {
-    let mut props = BTreeSet::new();
+fn extract_css_rules(css: &str) -> Vec<CssRule> {
+    let comment_re = Regex::new(r"(?s)/\*.*?\*/").unwrap();
+    let mut rules = Vec::new();
 
-    // Find all innermost blocks via a brace stack.
-    let mut stack: Vec<usize> = Vec::new();
+    // (open brace index, prelude start, has nested block)
+    let mut stack: Vec<(usize, usize, bool)> = Vec::new();
+    let mut prelude_start = 0usize;
     let mut in_single = false;
     let mut in_double = false;
     let mut escape = false;
@@ -686,32 +6535,41 @@ fn extract_decl_properties(css: &str) -> BTreeSet<String> {
         }
 
         match ch {
-            '\\' => {
-                escape = true;
-            }
-            '\'' if !in_double => {
-                in_single = !in_single;
-            }
-            '"' if !in_single => {
-                in_double = !in_double;
-            }
+            '\\' => escape = true,
+            '\'' if !in_double => in_single = !in_single,
+            '"' if !in_single => in_double = !in_double,
             '{' if !in_single && !in_double => {
-                stack.push(i);
+                if let Some(parent) = stack.last_mut() {
+                    parent.2 = true;
+                }
+                stack.push((i, prelude_start, false));
+                prelude_start = i + 1;
             }
             '}' if !in_single && !in_double => {
-                if let Some(start) = stack.pop() {
-                    let block = &css[start + 1..i];
-                    extract_properties_from_block(block, &mut props);
+                if let Some((start, prelude, nested)) = stack.pop() {
+                    if !nested {
+                        let selector = comment_re.replace_all(&css[prelude..start], "");
+                        let mut decls = Vec::new();
+                        extract_properties_from_block(&css[start + 1..i], start + 1, &mut decls);
+                        rules.push(CssRule {
+                            selector: normalize_selector(&selector),
+                            decls,
+                        });
+                    }
                 }
+                prelude_start = i + 1;
             }
+            ';' if !in_single && !in_double => prelude_start = i + 1,
             _ => {}
         }
     }
 
-    props
+    rules
 }
 
-fn extract_properties_from_block(block: &str, props: &mut BTreeSet<String>) {
+/// Push `(offset, prop)` for each declaration in `block`; `base` is the offset
+/// of the block within the stylesheet.
+fn extract_properties_from_block(block: &str, base: usize, props: &mut Vec<(usize, String)>) {
     // State machine that extracts `prop:` where `:` is not inside quotes/paren.
     let mut i = 0usize;
     let bytes = block.as_bytes();
@@ -808,7 +6666,229 @@ fn extract_properties_from_block(block: &str, props: &mut BTreeSet<String>) {
         }
 
         if !prop.is_empty() {
-            props.insert(prop);
+            props.push((base + prop_start, prop));
+        }
+    }
+}
+
+/// A style rule from build output: its selector and declarations with byte offsets.
+#[derive(Debug)]
+struct CssRule {
+    selector: String,
+    decls: Vec<(usize, String)>,
+}
+
+fn normalize_selector(selector: &str) -> String {
+    selector.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// A decoded source map (v3): for each generated line, segments of
+/// `(generated column, source index, original line, original column)`.
+#[derive(Debug, Default)]
+struct SourceMap {
+    sources: Vec<String>,
+    lines: Vec<Vec<(u32, u32, u32, u32)>>,
+}
+
+impl SourceMap {
+    fn parse(raw: &str) -> Result<Self> {
+        let json: serde_json::Value = serde_json::from_str(raw).context("parse source map JSON")?;
+        let source_root = json.get("sourceRoot").and_then(|v| v.as_str()).unwrap_or("");
+        let sources = json
+            .get("sources")
+            .and_then(|v| v.as_array())
+            .map(|items| {
+                items
+                    .iter()
+                    .map(|s| format!("{source_root}{}", s.as_str().unwrap_or("")))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mappings = json
+            .get("mappings")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("source map has no `mappings`"))?;
+
+        let mut lines = Vec::new();
+        let (mut src, mut src_line, mut src_col) = (0i64, 0i64, 0i64);
+        for line in mappings.split(';') {
+            let mut segments = Vec::new();
+            let mut gen_col = 0i64;
+            for segment in line.split(',').filter(|s| !s.is_empty()) {
+                let fields = decode_vlq(segment)?;
+                gen_col += fields[0];
+                if fields.len() >= 4 {
+                    src += fields[1];
+                    src_line += fields[2];
+                    src_col += fields[3];
+                    segments.push((gen_col as u32, src as u32, src_line as u32, src_col as u32));
+                }
+            }
+            lines.push(segments);
+        }
+
+        Ok(Self { sources, lines })
+    }
+
+    /// Load the map referenced by `sourceMappingURL` (or the `<file>.map`
+    /// sibling). Inline `data:` maps are not supported.
+    fn load_for(generated: &Path, text: &str) -> Option<Self> {
+        let url_re = Regex::new(r"[#@]\s*sourceMappingURL=([^\s*]+)").unwrap();
+        let candidate = match url_re.captures_iter(text).last() {
+            Some(caps) if !caps[1].starts_with("data:") => generated.parent()?.join(&caps[1]),
+            _ => PathBuf::from(format!("{}.map", generated.display())),
+        };
+        let raw = fs::read_to_string(candidate).ok()?;
+        Self::parse(&raw).ok()
+    }
+
+    /// Original source and 0-based line/column for a 0-based generated position.
+    fn lookup(&self, line: usize, col: usize) -> Option<(&str, u32, u32)> {
+        let segments = self.lines.get(line)?;
+        let idx = segments.partition_point(|s| s.0 as usize <= col);
+        let seg = segments.get(idx.checked_sub(1)?)?;
+        Some((self.sources.get(seg.1 as usize)?.as_str(), seg.2, seg.3))
+    }
+}
+
+fn decode_vlq(segment: &str) -> Result<Vec<i64>> {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut values = Vec::new();
+    let (mut value, mut shift) = (0i64, 0u32);
+    for b in segment.bytes() {
+        let digit = ALPHABET
+            .iter()
+            .position(|&a| a == b)
+            .ok_or_else(|| anyhow!("invalid base64 VLQ character `{}`", b as char))? as i64;
+        value += (digit & 31) << shift;
+        if digit & 32 != 0 {
+            shift += 5;
+            continue;
+        }
+        values.push(if value & 1 == 1 { -(value >> 1) } else { value >> 1 });
+        value = 0;
+        shift = 0;
+    }
+    Ok(values)
+}
+
+/// 0-based line and column of a byte offset, as source maps count them.
+fn line_col0(text: &str, index: usize) -> (usize, usize) {
+    let (line, col) = line_col_from_index(text, index);
+    (line - 1, col - 1)
+}
+
+/// npm package a (source map or file) path belongs to: the segment after the
+/// last `node_modules/`, with the scope for `@scope/name` packages.
+fn npm_package_of(path: &str) -> Option<String> {
+    let path = path.replace('\\', "/");
+    let rest = &path[path.rfind("node_modules/")? + "node_modules/".len()..];
+    let mut parts = rest.split('/');
+    let first = parts.next()?;
+    if first.starts_with('@') {
+        Some(format!("{first}/{}", parts.next()?))
+    } else {
+        Some(first.to_string())
+    }
+}
+
+/// Label used for violations that come from the project's own sources.
+const PROJECT_ORIGIN: &str = "(project)";
+
+/// How a dist CSS declaration was traced back to its origin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum OriginVia {
+    SourceMap,
+    PackageCss,
+    Unattributed,
+}
+
+impl OriginVia {
+    fn label(self) -> &'static str {
+        match self {
+            OriginVia::SourceMap => "source map",
+            OriginVia::PackageCss => "matched package CSS",
+            OriginVia::Unattributed => "no match in imported packages",
+        }
+    }
+}
+
+/// `(selector, property)` pairs declared by the CSS shipped in imported npm
+/// packages, for attributing dist declarations when no source map exists.
+#[derive(Debug, Default)]
+struct PackageCssIndex {
+    by_rule: BTreeMap<(String, String), BTreeSet<String>>,
+}
+
+impl PackageCssIndex {
+    fn build(project_root: &Path, scan_files: &ScanFiles) -> Result<Self> {
+        let mut index = Self::default();
+        let node_modules = project_root.join("node_modules");
+        if !node_modules.is_dir() {
+            return Ok(index);
+        }
+
+        for package in imported_packages(scan_files)? {
+            let dir = node_modules.join(&package);
+            if !dir.is_dir() {
+                continue;
+            }
+            for entry in WalkDir::new(&dir)
+                .into_iter()
+                .filter_entry(|e| e.depth() == 0 || e.file_name() != "node_modules")
+                .filter_map(|e| e.ok())
+            {
+                if entry.path().extension().and_then(|e| e.to_str()) != Some("css") {
+                    continue;
+                }
+                let Ok(css) = fs::read_to_string(entry.path()) else {
+                    continue;
+                };
+                for rule in extract_css_rules(&css) {
+                    for (_, prop) in rule.decls {
+                        index
+                            .by_rule
+                            .entry((rule.selector.clone(), prop))
+                            .or_default()
+                            .insert(package.clone());
+                    }
+                }
+            }
+        }
+
+        Ok(index)
+    }
+
+    fn attribute(&self, selector: &str, prop: &str) -> Option<String> {
+        // Only an exact rule match counts: a package that merely declares the same
+        // property elsewhere says nothing about where this declaration came from.
+        let packages = self.by_rule.get(&(selector.to_string(), prop.to_string()))?;
+        Some(packages.iter().cloned().collect::<Vec<_>>().join(" | "))
+    }
+}
+
+/// Bare module specifiers imported by the scanned sources (`react`,
+/// `@base-ui/react`), reduced to package names.
+fn imported_packages(scan_files: &ScanFiles) -> Result<BTreeSet<String>> {
+    let import_re =
+        Regex::new(r#"(?:\bfrom|\bimport|\brequire\(|@import)\s*(?:url\()?\s*['"]([^'"./][^'"]*)['"]"#).unwrap();
+    let mut packages = BTreeSet::new();
+    for path in scan_files.with_extensions(&["ts", "tsx", "js", "jsx", "css", "vue"]) {
+        let text = fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+        for caps in import_re.captures_iter(&text) {
+            let spec = &caps[1];
+            let mut parts = spec.split('/');
+            let first = parts.next().unwrap_or_default();
+            let name = if first.starts_with('@') {
+                match parts.next() {
+                    Some(second) => format!("{first}/{second}"),
+                    None => continue,
+                }
+            } else {
+                first.to_string()
+            };
+            packages.insert(name);
         }
     }
+    Ok(packages)
 }
@@ -0,0 +1,1532 @@
+#!/usr/bin/env -S rust-script --base-path . --clear-cache
+
+//! ```cargo
+//! [dependencies]
+//! anyhow = "1"
+//! clap = { version = "4", features = ["derive"] }
+//! serde = { version = "1", features = ["derive"] }
+//! serde_json = { version = "1", features = ["preserve_order"] }
+//! gif = "0.14"
+//! png = "0.18"
+//! rquickjs = "0.9"
+//! oxc_allocator = "0.110"
+//! oxc_ast = "0.110"
+//! oxc_ast_visit = "0.110"
+//! oxc_parser = "0.110"
+//! oxc_span = "0.110"
+//! ```
+
+use anyhow::{anyhow, bail, Context as _, Result};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use oxc_allocator::Allocator;
+use oxc_ast::ast::{
+    Class, FormalParameter, Statement, TSAsExpression, TSNonNullExpression, TSSatisfiesExpression,
+    TSTypeAnnotation, TSTypeParameterDeclaration, TSTypeParameterInstantiation,
+};
+use oxc_ast_visit::{walk, Visit};
+use oxc_parser::Parser as JsParser;
+use oxc_span::{GetSpan, SourceType, Span};
+use rquickjs::{Context, FromJs, Runtime};
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::Cell,
+    collections::BTreeMap,
+    fs,
+    io::IsTerminal,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+/// Effect catalog, relative to the frontend root.
+const EFFECTS_DIR: &str = "public/effects";
+
+/// Runtime bundled in front of every effect (same as `just compile-effects`).
+const EFFECT_RUNTIME_FILE: &str = "effect-runtime.ts";
+
+/// Golden-frame snapshots, relative to an effect directory.
+const SNAPSHOTS_DIR: &str = "snapshots";
+
+/// Wall-clock budget for one call into the engine; catches endless loops.
+const CALL_TIME_LIMIT: Duration = Duration::from_secs(2);
+
+#[derive(Parser, Debug)]
+#[command(name = "led-effects")]
+#[command(about = "Runs LED effects headlessly in an embedded QuickJS engine")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run one effect and dump its LED buffer after every tick.
+    Run(RunArgs),
+    /// Replay `<id>/snapshots/*.json` and compare against the recorded frames.
+    Test(TestArgs),
+    /// Fuzz lifecycle and `setConfig` call sequences and check the machine contract.
+    Fuzz(FuzzArgs),
+    /// Render an effect on a device layout to an animated GIF or PNG sprite sheet.
+    Render(RenderArgs),
+}
+
+#[derive(Args, Debug)]
+struct RunArgs {
+    /// Effect id (directory under public/effects).
+    id: String,
+
+    /// Number of frames (ticks) to run.
+    #[arg(long, default_value_t = 10)]
+    frames: usize,
+
+    /// Config object passed to `createEffect`, as JSON, e.g. '{"ledCount":12}'.
+    #[arg(long, value_name = "JSON", default_value = "{}")]
+    config: String,
+
+    /// `setConfig` call after `start` (repeatable): `waveWidth=3`,
+    /// `color={"mode":"rgb","r":255,"g":0,"b":0}`. Values that are not JSON are strings.
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    set: Vec<String>,
+
+    /// Output format.
+    #[arg(long, value_enum, default_value = "text")]
+    format: DumpFormat,
+
+    /// Also record the run as `<id>/snapshots/<NAME>.json`.
+    #[arg(long, value_name = "NAME")]
+    snapshot: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct TestArgs {
+    /// Effect ids to test (default: every effect with snapshots).
+    ids: Vec<String>,
+
+    /// Rewrite the expected frames of every replayed snapshot with the current output.
+    #[arg(long)]
+    update: bool,
+}
+
+#[derive(Args, Debug)]
+struct FuzzArgs {
+    /// Effect ids to fuzz (default: every effect in manifest.json).
+    ids: Vec<String>,
+
+    /// Seed for the generated sequences (default: time-based; printed so a failure can be replayed).
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Call sequences per effect.
+    #[arg(long, default_value_t = 200)]
+    runs: usize,
+
+    /// Calls per sequence.
+    #[arg(long, default_value_t = 100)]
+    steps: usize,
+}
+
+#[derive(Args, Debug)]
+struct RenderArgs {
+    /// Effect id (directory under public/effects).
+    id: String,
+
+    /// Device id (directory under public/devices; default: first in its manifest.json).
+    #[arg(long)]
+    device: Option<String>,
+
+    /// Number of frames (ticks) to render.
+    #[arg(long, default_value_t = 40, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    frames: usize,
+
+    /// Config object passed to `createEffect`; `ledCount` comes from the device.
+    #[arg(long, value_name = "JSON", default_value = "{}")]
+    config: String,
+
+    /// `setConfig` call after `start` (repeatable), as for `run`.
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    set: Vec<String>,
+
+    /// Output file: `.gif` (animation) or `.png` (sprite sheet).
+    /// Default: build/effects/<id>-<device>.gif under the frontend root.
+    #[arg(long, value_name = "PATH")]
+    out: Option<PathBuf>,
+
+    /// Pixels per mm of the device canvas.
+    #[arg(long, default_value_t = 3.0, value_parser = parse_scale)]
+    scale: f64,
+
+    /// Preview theme.
+    #[arg(long, value_enum, default_value = "dark")]
+    theme: Theme,
+
+    /// Frames per row of a PNG sprite sheet.
+    #[arg(long, default_value_t = 8)]
+    columns: usize,
+}
+
+/// `--scale`: a finite number of pixels per mm above zero.
+fn parse_scale(raw: &str) -> std::result::Result<f64, String> {
+    match raw.parse::<f64>() {
+        Ok(scale) if scale.is_finite() && scale > 0.0 => Ok(scale),
+        Ok(_) => Err("must be a number greater than 0".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+enum Theme {
+    Dark,
+    Light,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+enum DumpFormat {
+    /// One line per frame: `frame N  rrggbb rrggbb ...`.
+    Text,
+    /// `{"effect", "config", "frames": [[r, g, b, ...], ...]}`.
+    Json,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let cwd = std::env::current_dir().context("get current working directory")?;
+    let frontend_root = locate_frontend_root(&cwd)?;
+
+    match cli.command {
+        Command::Run(args) => run_effect(&frontend_root, &args),
+        Command::Test(args) => {
+            if !test_effects(&frontend_root, &args)? {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        Command::Fuzz(args) => {
+            if !fuzz_effects(&frontend_root, &args)? {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        Command::Render(args) => render_effect(&frontend_root, &args),
+    }
+}
+
+fn run_effect(frontend_root: &Path, args: &RunArgs) -> Result<()> {
+    let config: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(&args.config).with_context(|| format!("--config is not a JSON object: {}", args.config))?;
+    let mut set = serde_json::Map::new();
+    for assignment in args.set.iter() {
+        let (key, value) = parse_assignment(assignment)?;
+        set.insert(key.to_string(), value);
+    }
+    let frames = render_frames(frontend_root, &args.id, &config, &set, args.frames)?;
+
+    if let Some(name) = &args.snapshot {
+        let path = frontend_root.join(EFFECTS_DIR).join(&args.id).join(SNAPSHOTS_DIR).join(format!("{name}.json"));
+        let snapshot = Snapshot { config: config.clone(), set, frames: frames.iter().map(|f| frame_hex(f)).collect() };
+        snapshot.write(&path)?;
+        eprintln!("[led-effects] wrote {}", path.strip_prefix(frontend_root).unwrap_or(&path).display());
+    }
+
+    match args.format {
+        DumpFormat::Text => {
+            for (i, frame) in frames.iter().enumerate() {
+                println!("frame {i}  {}", frame_hex(frame));
+            }
+        }
+        DumpFormat::Json => {
+            println!(
+                "{{\"effect\":{},\"config\":{},\"frames\":[",
+                serde_json::Value::from(args.id.as_str()),
+                serde_json::Value::Object(config)
+            );
+            for (i, frame) in frames.iter().enumerate() {
+                let sep = if i + 1 < frames.len() { "," } else { "" };
+                println!("{}{sep}", serde_json::Value::from(frame.clone()));
+            }
+            println!("]}}");
+        }
+    }
+    Ok(())
+}
+
+/// Run effect `id`: `createEffect(config)`, `start()`, each `set` entry as
+/// `setConfig`, then `frames` ticks, capturing the LED buffer after each one.
+fn render_frames(
+    frontend_root: &Path,
+    id: &str,
+    config: &serde_json::Map<String, serde_json::Value>,
+    set: &serde_json::Map<String, serde_json::Value>,
+    frames: usize,
+) -> Result<Vec<Vec<u8>>> {
+    let runner = start_effect(frontend_root, id, config, set)?;
+    capture_frames(&runner, frames)
+}
+
+/// A running machine for effect `id`: `createEffect(config)`, `start()`, then
+/// each `set` entry as `setConfig`.
+fn start_effect(
+    frontend_root: &Path,
+    id: &str,
+    config: &serde_json::Map<String, serde_json::Value>,
+    set: &serde_json::Map<String, serde_json::Value>,
+) -> Result<EffectRunner> {
+    let bundle = compile_effect(frontend_root, id)?;
+    let runner = EffectRunner::new(bundle).with_context(|| format!("load effect `{id}`"))?;
+    runner.create(&serde_json::Value::Object(config.clone()))?;
+    runner.call("start")?;
+    for (key, value) in set {
+        runner.set_config(key, value)?;
+    }
+    Ok(runner)
+}
+
+/// Tick `frames` times, capturing the LED buffer after each tick.
+fn capture_frames(runner: &EffectRunner, frames: usize) -> Result<Vec<Vec<u8>>> {
+    let mut out = Vec::with_capacity(frames);
+    for frame in 0..frames {
+        runner.call("tick")?;
+        let leds = runner.leds()?;
+        out.push(frame_bytes(&leds).with_context(|| format!("frame {frame}"))?);
+    }
+    Ok(out)
+}
+
+/// One recorded run of an effect: `public/effects/<id>/snapshots/<name>.json`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Snapshot {
+    /// Passed to `createEffect`.
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    config: serde_json::Map<String, serde_json::Value>,
+    /// `setConfig` calls after `start`, in order.
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    set: serde_json::Map<String, serde_json::Value>,
+    /// Expected LED buffer after each tick, as `rrggbb rrggbb ...`.
+    frames: Vec<String>,
+}
+
+impl Snapshot {
+    fn load(path: &Path) -> Result<Self> {
+        let raw = fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+        serde_json::from_str(&raw).with_context(|| format!("parse {}", path.display()))
+    }
+
+    fn write(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+        }
+        let mut text = serde_json::to_string_pretty(self)?;
+        text.push('\n');
+        fs::write(path, text).with_context(|| format!("write {}", path.display()))
+    }
+}
+
+/// Replay every snapshot of the selected effects. Returns false if any failed.
+fn test_effects(frontend_root: &Path, args: &TestArgs) -> Result<bool> {
+    let effects_dir = frontend_root.join(EFFECTS_DIR);
+    let ids = if args.ids.is_empty() {
+        let mut ids = Vec::new();
+        for entry in fs::read_dir(&effects_dir).with_context(|| format!("read {}", effects_dir.display()))? {
+            let entry = entry?;
+            if entry.path().join(SNAPSHOTS_DIR).is_dir() {
+                ids.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        ids.sort();
+        ids
+    } else {
+        args.ids.clone()
+    };
+
+    let color = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    let mut total = 0;
+    let mut failed = 0;
+    for id in ids.iter() {
+        let dir = effects_dir.join(id).join(SNAPSHOTS_DIR);
+        let mut paths: Vec<PathBuf> = match fs::read_dir(&dir) {
+            Ok(entries) => entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        paths.sort();
+        if paths.is_empty() {
+            println!("[led-effects] {id}: no snapshots in {EFFECTS_DIR}/{id}/{SNAPSHOTS_DIR}");
+            continue;
+        }
+
+        for path in paths.iter() {
+            total += 1;
+            let name = format!("{id}/{SNAPSHOTS_DIR}/{}", path.file_name().unwrap_or_default().to_string_lossy());
+            let outcome = Snapshot::load(path).and_then(|mut snapshot| {
+                let actual = render_frames(frontend_root, id, &snapshot.config, &snapshot.set, snapshot.frames.len())?;
+                if args.update {
+                    snapshot.frames = actual.iter().map(|f| frame_hex(f)).collect();
+                    snapshot.write(path)?;
+                    return Ok(None);
+                }
+                let expected = snapshot
+                    .frames
+                    .iter()
+                    .enumerate()
+                    .map(|(i, f)| parse_frame_hex(f).with_context(|| format!("frame {i}")))
+                    .collect::<Result<Vec<_>>>()
+                    .with_context(|| format!("parse {}", path.display()))?;
+                Ok(frame_mismatch(&expected, &actual, color))
+            });
+            match outcome {
+                Ok(None) if args.update => println!("[led-effects] {name} updated"),
+                Ok(None) => println!("[led-effects] {name} OK"),
+                Ok(Some(diff)) => {
+                    failed += 1;
+                    println!("[led-effects] {name} FAILED\n{diff}");
+                }
+                Err(err) => {
+                    failed += 1;
+                    println!("[led-effects] {name} FAILED\n  {err:#}");
+                }
+            }
+        }
+    }
+
+    if failed > 0 {
+        println!("[led-effects] {failed} of {total} snapshots failed (rerun with --update if the change is intended)");
+        return Ok(false);
+    }
+    println!("[led-effects] {total} snapshots OK");
+    Ok(true)
+}
+
+/// Describe the first frame/LED where `actual` differs from `expected`, with
+/// both frames printed and the differing LEDs highlighted.
+fn frame_mismatch(expected: &[Vec<u8>], actual: &[Vec<u8>], color: bool) -> Option<String> {
+    let (frame, want, got) = expected.iter().zip(actual.iter()).enumerate().find(|(_, (e, a))| e != a).map(|(i, (e, a))| (i, e, a))?;
+    if want.len() != got.len() {
+        return Some(format!("  frame {frame}: expected {} LEDs, got {}", want.len() / 3, got.len() / 3));
+    }
+    let led = want.chunks(3).zip(got.chunks(3)).position(|(e, a)| e != a).unwrap_or(0);
+    let pixel = |rgb: &[u8]| {
+        let hex: String = rgb.iter().map(|c| format!("{c:02x}")).collect();
+        if color {
+            format!("{hex} \x1b[48;2;{};{};{}m  \x1b[0m", rgb[0], rgb[1], rgb[2])
+        } else {
+            hex
+        }
+    };
+    let row = |frame: &[u8], other: &[u8], paint: &str| {
+        frame
+            .chunks(3)
+            .zip(other.chunks(3))
+            .map(|(rgb, o)| {
+                let hex: String = rgb.iter().map(|c| format!("{c:02x}")).collect();
+                if color && rgb != o {
+                    format!("\x1b[1;{paint}m{hex}\x1b[0m")
+                } else {
+                    hex
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let o = led * 3;
+    Some(format!(
+        "  frame {frame}, LED {led}: expected {}, got {}\n  - expected  {}\n  + actual    {}",
+        pixel(&want[o..o + 3]),
+        pixel(&got[o..o + 3]),
+        row(want, got, "31"),
+        row(got, want, "32"),
+    ))
+}
+
+/// `public/effects/manifest.json`.
+#[derive(Debug, Deserialize)]
+struct EffectManifest {
+    effects: Vec<String>,
+}
+
+/// The parts of `<id>/ui.json` that decide which `setConfig` calls the app can make.
+#[derive(Debug, Default, Deserialize)]
+struct EffectUi {
+    #[serde(default)]
+    bridge: EffectBridge,
+    speed: Option<SpeedRange>,
+    #[serde(default)]
+    elements: serde_json::Map<String, serde_json::Value>,
+    #[serde(default)]
+    state: serde_json::Value,
+}
+
+/// Mirrors `EffectBridgeConfig` in src/effects/EffectRenderer.tsx.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EffectBridge {
+    #[serde(default)]
+    color_keys: Vec<String>,
+    #[serde(default)]
+    scale_keys: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SpeedRange {
+    min: f64,
+    max: f64,
+}
+
+/// Speed range used when ui.json declares none.
+const DEFAULT_SPEED_RANGE: (f64, f64) = (10.0, 1000.0);
+
+/// Largest `ledCount` the fuzzer passes to `createEffect`.
+const MAX_FUZZ_LED_COUNT: u64 = 64;
+
+/// SplitMix64; small, and the same sequence on every platform for a given seed.
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+
+    /// Uniform in `[0, 1)`.
+    fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// A value a slider over `min..=max` can produce: mostly the endpoints,
+    /// whole numbers and arbitrary fractions (sliders without `step` are continuous).
+    fn slider(&mut self, min: f64, max: f64) -> f64 {
+        match self.below(8) {
+            0 => min,
+            1 => max,
+            2..=4 => (min + self.unit() * (max - min)).round().clamp(min, max),
+            _ => ((min + self.unit() * (max - min)) * 1000.0).round() / 1000.0,
+        }
+    }
+}
+
+/// `v` as JSON, without a trailing `.0` on whole numbers.
+fn json_number(v: f64) -> serde_json::Value {
+    if v.fract() == 0.0 && v.abs() < 1e15 {
+        (v as i64).into()
+    } else {
+        v.into()
+    }
+}
+
+/// One call on the effect machine.
+#[derive(Clone, Debug)]
+enum Op {
+    Start,
+    Pause,
+    Resume,
+    Stop,
+    Tick,
+    SetSpeed(f64),
+    SetConfig(String, serde_json::Value),
+}
+
+impl Op {
+    /// The call as JavaScript on `m`, e.g. `m.setConfig("waveWidth", 3)`.
+    fn js(&self, machine: &str) -> String {
+        match self {
+            Op::Start => format!("{machine}.start()"),
+            Op::Pause => format!("{machine}.pause()"),
+            Op::Resume => format!("{machine}.resume()"),
+            Op::Stop => format!("{machine}.stop()"),
+            Op::Tick => format!("{machine}.tick()"),
+            Op::SetSpeed(ms) => format!("{machine}.setSpeed({})", json_number(*ms)),
+            Op::SetConfig(key, value) => {
+                format!("{machine}.setConfig({}, {value})", serde_json::Value::from(key.as_str()))
+            }
+        }
+    }
+
+    /// `status` after this call, per `createBaseMachine` (idle -> running <-> paused -> idle).
+    fn next_status<'s>(&self, status: &'s str) -> &'s str {
+        match (self, status) {
+            (Op::Start, "idle") => "running",
+            (Op::Pause, "running") => "paused",
+            (Op::Resume, "paused") => "running",
+            (Op::Stop, _) => "idle",
+            _ => status,
+        }
+    }
+}
+
+/// Generates `createEffect` configs and call sequences for one effect from its ui.json.
+struct SequenceGen {
+    speed: (f64, f64),
+    /// `(state key, min, max)` for every `$bindState: "/effect/<key>"` control.
+    params: Vec<(String, f64, f64)>,
+    defaults: serde_json::Map<String, serde_json::Value>,
+    color_keys: Vec<String>,
+    scale_keys: serde_json::Map<String, serde_json::Value>,
+}
+
+impl SequenceGen {
+    fn new(ui: EffectUi) -> Self {
+        let defaults = ui.state.get("effect").and_then(|v| v.as_object()).cloned().unwrap_or_default();
+        let mut params: Vec<(String, f64, f64)> = Vec::new();
+        for element in ui.elements.values() {
+            let kind = element.get("type").and_then(|v| v.as_str()).unwrap_or_default();
+            let Some(props) = element.get("props").and_then(|v| v.as_object()) else {
+                continue;
+            };
+            for (prop, value) in props {
+                let Some(key) = value
+                    .get("$bindState")
+                    .and_then(|v| v.as_str())
+                    .and_then(|p| p.strip_prefix("/effect/"))
+                else {
+                    continue;
+                };
+                let (min, max) = match (kind, prop.as_str()) {
+                    ("ColorHSV", "hue") => (0.0, 360.0),
+                    ("ColorHSV", _) => (0.0, 100.0),
+                    _ => (
+                        props.get("min").and_then(|v| v.as_f64()).unwrap_or(0.0),
+                        props.get("max").and_then(|v| v.as_f64()).unwrap_or(100.0),
+                    ),
+                };
+                if !params.iter().any(|(k, _, _)| k == key) {
+                    params.push((key.to_string(), min, max));
+                }
+            }
+        }
+        // State the UI does not expose can still arrive from the assistant's set_config tool.
+        for (key, value) in defaults.iter() {
+            if let Some(v) = value.as_f64() {
+                if !params.iter().any(|(k, _, _)| k == key) {
+                    params.push((key.clone(), 0.0, (v * 2.0).max(1.0)));
+                }
+            }
+        }
+
+        Self {
+            speed: ui.speed.map(|s| (s.min, s.max)).unwrap_or(DEFAULT_SPEED_RANGE),
+            params,
+            defaults,
+            color_keys: ui.bridge.color_keys,
+            scale_keys: ui.bridge.scale_keys,
+        }
+    }
+
+    /// What the host passes to `createEffect`: `ledCount` and `speed`, each sometimes left out.
+    fn config(&self, rng: &mut Rng) -> serde_json::Map<String, serde_json::Value> {
+        let mut config = serde_json::Map::new();
+        if rng.below(4) != 0 {
+            config.insert("ledCount".to_string(), (1 + rng.below(MAX_FUZZ_LED_COUNT)).into());
+        }
+        if rng.below(4) != 0 {
+            config.insert("speed".to_string(), json_number(rng.slider(self.speed.0, self.speed.1)));
+        }
+        config
+    }
+
+    fn ops(&self, rng: &mut Rng, steps: usize) -> Vec<Op> {
+        let mut state = self.defaults.clone();
+        (0..steps)
+            .map(|_| match rng.below(20) {
+                0..=1 => Op::Start,
+                2 => Op::Pause,
+                3 => Op::Resume,
+                4 => Op::Stop,
+                5 => Op::SetSpeed(rng.slider(self.speed.0, self.speed.1)),
+                6 => Op::SetConfig("speed".to_string(), json_number(rng.slider(self.speed.0, self.speed.1))),
+                7..=9 if !self.params.is_empty() => {
+                    let (key, min, max) = &self.params[rng.below(self.params.len() as u64) as usize];
+                    let value = rng.slider(*min, *max);
+                    state.insert(key.clone(), json_number(value));
+                    self.bridge(key, value, &state)
+                }
+                _ => Op::Tick,
+            })
+            .collect()
+    }
+
+    /// The `setConfig` call the app makes when state key `key` changes
+    /// (same mapping as `createStateChangeHandler` in EffectRenderer.tsx).
+    fn bridge(&self, key: &str, value: f64, state: &serde_json::Map<String, serde_json::Value>) -> Op {
+        if self.color_keys.iter().any(|k| k == key) {
+            let get = |k: &str, default: f64| state.get(k).and_then(|v| v.as_f64()).unwrap_or(default);
+            let color = serde_json::json!({
+                "mode": "hsv",
+                "h": json_number(get("hue", 0.0)),
+                "s": json_number(get("saturation", 100.0) / 100.0),
+                "v": json_number(get("brightness", 100.0) / 100.0),
+            });
+            return Op::SetConfig("color".to_string(), color);
+        }
+        let scaled = match self.scale_keys.get(key).and_then(|v| v.as_f64()) {
+            Some(scale) => value * scale,
+            None => value,
+        };
+        Op::SetConfig(key.to_string(), json_number(scaled))
+    }
+}
+
+/// A broken machine contract, found at `step` (`None`: in `createEffect`).
+#[derive(Debug)]
+struct Violation {
+    step: Option<usize>,
+    kind: &'static str,
+    detail: String,
+}
+
+/// Run `ops` on a fresh machine and return the first contract violation.
+fn check_sequence(
+    runner: &EffectRunner,
+    config: &serde_json::Map<String, serde_json::Value>,
+    ops: &[Op],
+) -> Option<Violation> {
+    let thrown = |step: Option<usize>, err: anyhow::Error| Violation {
+        step,
+        kind: if format!("{err:#}").contains("interrupted") { "timeout" } else { "exception" },
+        detail: format!("{err:#}"),
+    };
+
+    if let Err(err) = runner.create(&serde_json::Value::Object(config.clone())) {
+        return Some(thrown(None, err));
+    }
+    let led_count = match runner.led_count() {
+        Ok(n) => n,
+        Err(err) => return Some(thrown(None, err)),
+    };
+    if let Some(want) = config.get("ledCount").and_then(|v| v.as_f64()) {
+        if led_count != want {
+            return Some(Violation {
+                step: None,
+                kind: "ledCount",
+                detail: format!("createEffect was given ledCount {want}, machine.ledCount is {led_count}"),
+            });
+        }
+    }
+
+    let mut status = "idle".to_string();
+    let mut before: Option<Vec<u8>> = None;
+    for step in 0..=ops.len() {
+        let op = step.checked_sub(1).map(|i| &ops[i]);
+        let at = op.map(|_| step - 1);
+        let call = op.map(|op| op.js("m")).unwrap_or_else(|| "createEffect".to_string());
+
+        if let Some(op) = op {
+            if let Err(err) = runner.apply(op) {
+                return Some(thrown(at, err));
+            }
+            status = op.next_status(&status).to_string();
+        }
+
+        let actual = match runner.status() {
+            Ok(s) => s,
+            Err(err) => return Some(thrown(at, err)),
+        };
+        if actual != status {
+            let detail = format!("after {call}: status is `{actual}`, expected `{status}`");
+            return Some(Violation { step: at, kind: "lifecycle", detail });
+        }
+        match runner.buffer_len() {
+            Ok(len) if len == led_count * 3.0 => {}
+            Ok(len) => {
+                let detail = format!("after {call}: leds.length is {len}, expected ledCount*3 = {}", led_count * 3.0);
+                return Some(Violation { step: at, kind: "buffer resized", detail });
+            }
+            Err(err) => return Some(thrown(at, err)),
+        }
+        let leds = match runner.leds() {
+            Ok(leds) => leds,
+            Err(err) => return Some(thrown(at, err)),
+        };
+        let bytes = match frame_bytes(&leds) {
+            Ok(bytes) => bytes,
+            Err(err) => return Some(Violation { step: at, kind: "bad channel", detail: format!("after {call}: {err}") }),
+        };
+
+        if let (Some(Op::Tick), Some(prev)) = (op, before.as_ref()) {
+            if *prev != bytes {
+                let detail = format!("{call} while {status} changed the LEDs");
+                return Some(Violation { step: at, kind: "tick while not running", detail });
+            }
+        }
+        if status == "idle" {
+            if let Some(led) = bytes.chunks(3).position(|rgb| rgb != [0, 0, 0]) {
+                let detail =
+                    format!("after {call}: LED {led} is {} while idle", frame_hex(&bytes[led * 3..led * 3 + 3]));
+                return Some(Violation { step: at, kind: "lit while idle", detail });
+            }
+        }
+        before = matches!(ops.get(step), Some(Op::Tick) if status != "running").then_some(bytes);
+    }
+    None
+}
+
+/// Drop calls one at a time while the sequence still fails the same way.
+fn shrink_sequence(
+    runner: &EffectRunner,
+    config: &serde_json::Map<String, serde_json::Value>,
+    mut ops: Vec<Op>,
+    mut violation: Violation,
+) -> (Vec<Op>, Violation) {
+    ops.truncate(violation.step.map_or(0, |s| s + 1));
+    let mut i = 0;
+    while i < ops.len() {
+        let mut candidate = ops.clone();
+        candidate.remove(i);
+        match check_sequence(runner, config, &candidate) {
+            Some(v) if v.kind == violation.kind => {
+                candidate.truncate(v.step.map_or(0, |s| s + 1));
+                ops = candidate;
+                violation = v;
+            }
+            _ => i += 1,
+        }
+    }
+    (ops, violation)
+}
+
+/// Fuzz every selected effect. Returns false if any contract violation was found.
+fn fuzz_effects(frontend_root: &Path, args: &FuzzArgs) -> Result<bool> {
+    let effects_dir = frontend_root.join(EFFECTS_DIR);
+    let ids = if args.ids.is_empty() {
+        let path = effects_dir.join("manifest.json");
+        let raw = fs::read_to_string(&path).with_context(|| format!("read {}", path.display()))?;
+        let manifest: EffectManifest = serde_json::from_str(&raw).with_context(|| format!("parse {}", path.display()))?;
+        manifest.effects
+    } else {
+        args.ids.clone()
+    };
+    let seed = args.seed.unwrap_or_else(|| {
+        std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0)
+    });
+    println!("[led-effects] fuzzing {} effects, seed {seed}", ids.len());
+
+    let mut failed = 0;
+    for id in ids.iter() {
+        let ui_path = effects_dir.join(id).join("ui.json");
+        let ui: EffectUi = match fs::read_to_string(&ui_path) {
+            Ok(raw) => serde_json::from_str(&raw).with_context(|| format!("parse {}", ui_path.display()))?,
+            Err(_) => EffectUi::default(),
+        };
+        let generator = SequenceGen::new(ui);
+        let runner = EffectRunner::new(compile_effect(frontend_root, id)?).with_context(|| format!("load effect `{id}`"))?;
+        runner.keep_raw_channels()?;
+
+        let mut seen: Vec<&'static str> = Vec::new();
+        for run in 0..args.runs {
+            let mut rng = Rng(seed ^ (run as u64).wrapping_mul(0x2545_f491_4f6c_dd1d));
+            let config = generator.config(&mut rng);
+            let ops = generator.ops(&mut rng, args.steps);
+            let Some(violation) = check_sequence(&runner, &config, &ops) else {
+                continue;
+            };
+            if seen.contains(&violation.kind) {
+                continue;
+            }
+            seen.push(violation.kind);
+
+            let (ops, violation) = shrink_sequence(&runner, &config, ops, violation);
+            println!("[led-effects] {id}: {} (run {run})", violation.kind);
+            for line in violation.detail.lines() {
+                println!("  {line}");
+            }
+            println!("  const m = createEffect({});", serde_json::Value::Object(config));
+            for op in ops.iter() {
+                println!("  {};", op.js("m"));
+            }
+        }
+        if seen.is_empty() {
+            println!("[led-effects] {id} OK ({} runs x {} calls)", args.runs, args.steps);
+        }
+        failed += seen.len();
+    }
+
+    if failed > 0 {
+        println!("[led-effects] {failed} contract violations (replay with --seed {seed})");
+        return Ok(false);
+    }
+    println!("[led-effects] fuzz OK");
+    Ok(true)
+}
+
+/// Device layouts, relative to the frontend root.
+const DEVICES_DIR: &str = "public/devices";
+
+/// Padding around the device in mm (same as `PADDING_MM` in DeviceCanvasView.tsx).
+const PADDING_MM: f64 = 15.0;
+
+/// Space between frames in a PNG sprite sheet, in px.
+const SHEET_GAP_PX: usize = 2;
+
+/// `public/devices/manifest.json`.
+#[derive(Debug, Deserialize)]
+struct DeviceManifest {
+    devices: Vec<String>,
+}
+
+/// `public/devices/<id>/config.json`; mirrors `DeviceConfig` in src/types/device.ts.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeviceConfig {
+    canvas: DeviceCanvas,
+    #[serde(default)]
+    rotor_guards: BTreeMap<String, DeviceRotorGuard>,
+    strips: Vec<DeviceStrip>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceCanvas {
+    width: f64,
+    height: f64,
+    origin: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceRotorGuard {
+    cx: f64,
+    cy: f64,
+    radius: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeviceStrip {
+    id: String,
+    led_count: usize,
+    leds: Vec<DeviceLed>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceLed {
+    index: usize,
+    x: f64,
+    y: f64,
+}
+
+/// Colors of the device preview, per theme (DeviceCanvasView.tsx).
+struct Palette {
+    background: [u8; 3],
+    /// `(rgb, alpha)` of rotor guards, the body hint and unlit LEDs.
+    guard: ([u8; 3], f64),
+    body: ([u8; 3], f64),
+    unlit: ([u8; 3], f64),
+}
+
+impl Theme {
+    fn palette(self) -> Palette {
+        match self {
+            Theme::Dark => Palette {
+                background: [0x0f, 0x17, 0x2a],
+                guard: ([148, 163, 184], 0.35),
+                body: ([148, 163, 184], 0.15),
+                unlit: ([148, 163, 184], 0.25),
+            },
+            Theme::Light => Palette {
+                background: [0xf8, 0xfa, 0xfc],
+                guard: ([100, 116, 139], 0.3),
+                body: ([100, 116, 139], 0.12),
+                unlit: ([100, 116, 139], 0.2),
+            },
+        }
+    }
+}
+
+/// An RGB image with anti-aliased shape drawing.
+struct Canvas {
+    width: usize,
+    height: usize,
+    rgb: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize, background: [u8; 3]) -> Self {
+        Self { width, height, rgb: background.repeat(width * height) }
+    }
+
+    fn blend(&mut self, x: usize, y: usize, color: [u8; 3], alpha: f64) {
+        let o = (y * self.width + x) * 3;
+        for (c, &src) in self.rgb[o..o + 3].iter_mut().zip(color.iter()) {
+            *c = (*c as f64 + (src as f64 - *c as f64) * alpha).round() as u8;
+        }
+    }
+
+    /// Blend `color` over every pixel within `reach` of `(cx, cy)`, with coverage from `coverage(distance)`.
+    fn shade(&mut self, cx: f64, cy: f64, reach: f64, color: [u8; 3], alpha: f64, coverage: impl Fn(f64) -> f64) {
+        let x0 = (cx - reach - 1.0).floor().max(0.0) as usize;
+        let y0 = (cy - reach - 1.0).floor().max(0.0) as usize;
+        let x1 = ((cx + reach + 1.0).ceil().max(0.0) as usize).min(self.width);
+        let y1 = ((cy + reach + 1.0).ceil().max(0.0) as usize).min(self.height);
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let d = ((x as f64 + 0.5 - cx).powi(2) + (y as f64 + 0.5 - cy).powi(2)).sqrt();
+                let a = coverage(d).clamp(0.0, 1.0) * alpha;
+                if a > 0.0 {
+                    self.blend(x, y, color, a);
+                }
+            }
+        }
+    }
+
+    fn fill_circle(&mut self, cx: f64, cy: f64, r: f64, (color, alpha): ([u8; 3], f64)) {
+        self.shade(cx, cy, r, color, alpha, |d| r + 0.5 - d);
+    }
+
+    fn stroke_circle(&mut self, cx: f64, cy: f64, r: f64, width: f64, (color, alpha): ([u8; 3], f64)) {
+        self.shade(cx, cy, r + width, color, alpha, |d| width / 2.0 + 0.5 - (d - r).abs());
+    }
+
+    fn fill_rect(&mut self, x0: f64, y0: f64, x1: f64, y1: f64, (color, alpha): ([u8; 3], f64)) {
+        let (width, height) = (self.width, self.height);
+        let clamp = |v: f64, max: usize| (v.round().max(0.0) as usize).min(max);
+        for y in clamp(y0, height)..clamp(y1, height) {
+            for x in clamp(x0, width)..clamp(x1, width) {
+                self.blend(x, y, color, alpha);
+            }
+        }
+    }
+}
+
+/// Draws LED frames onto a device's layout the way DeviceCanvasView.tsx does.
+struct DeviceRenderer {
+    device: DeviceConfig,
+    palette: Palette,
+    /// px per mm.
+    scale: f64,
+    width: usize,
+    height: usize,
+}
+
+impl DeviceRenderer {
+    fn new(device: DeviceConfig, theme: Theme, scale: f64) -> Result<Self> {
+        if device.canvas.origin != "center" {
+            bail!("canvas origin `{}` is not supported (expected `center`)", device.canvas.origin);
+        }
+        let width = ((device.canvas.width + PADDING_MM * 2.0) * scale).round() as usize;
+        let height = ((device.canvas.height + PADDING_MM * 2.0) * scale).round() as usize;
+        Ok(Self { device, palette: theme.palette(), scale, width, height })
+    }
+
+    /// LEDs across all strips; strips share one buffer, laid out one after another.
+    fn led_count(&self) -> usize {
+        self.device.strips.iter().map(|s| s.led_count).sum()
+    }
+
+    /// mm (center origin, y up) to px (top-left origin, y down).
+    fn to_pixel(&self, x: f64, y: f64) -> (f64, f64) {
+        (self.width as f64 / 2.0 + x * self.scale, self.height as f64 / 2.0 - y * self.scale)
+    }
+
+    fn draw(&self, leds: &[u8]) -> Canvas {
+        let mut canvas = Canvas::new(self.width, self.height, self.palette.background);
+        for guard in self.device.rotor_guards.values() {
+            let (cx, cy) = self.to_pixel(guard.cx, guard.cy);
+            canvas.stroke_circle(cx, cy, guard.radius * self.scale, 1.5, self.palette.guard);
+        }
+        let (bx0, by0) = self.to_pixel(-8.0, 6.0);
+        let (bx1, by1) = self.to_pixel(8.0, -6.0);
+        canvas.fill_rect(bx0, by0, bx1, by1, self.palette.body);
+
+        let led_radius = (2.5 * self.scale).max(2.0);
+        let mut offset = 0;
+        for strip in self.device.strips.iter() {
+            for led in strip.leds.iter() {
+                let o = (offset + led.index) * 3;
+                let rgb = leds.get(o..o + 3).map(|c| [c[0], c[1], c[2]]).unwrap_or([0, 0, 0]);
+                let (px, py) = self.to_pixel(led.x, led.y);
+                if rgb == [0, 0, 0] {
+                    canvas.fill_circle(px, py, led_radius * 0.7, self.palette.unlit);
+                } else {
+                    canvas.fill_circle(px, py, led_radius * 1.8, (rgb, 0.25));
+                    canvas.fill_circle(px, py, led_radius, (rgb, 1.0));
+                }
+            }
+            offset += strip.led_count;
+        }
+        canvas
+    }
+}
+
+/// Run an effect on a device layout and write the frames as an animated GIF
+/// or a PNG sprite sheet.
+fn render_effect(frontend_root: &Path, args: &RenderArgs) -> Result<()> {
+    let devices_dir = frontend_root.join(DEVICES_DIR);
+    let device_id = match &args.device {
+        Some(id) => id.clone(),
+        None => {
+            let path = devices_dir.join("manifest.json");
+            let raw = fs::read_to_string(&path).with_context(|| format!("read {}", path.display()))?;
+            let manifest: DeviceManifest =
+                serde_json::from_str(&raw).with_context(|| format!("parse {}", path.display()))?;
+            manifest.devices.into_iter().next().ok_or_else(|| anyhow!("no devices in {}", path.display()))?
+        }
+    };
+    let device_path = devices_dir.join(&device_id).join("config.json");
+    let raw = fs::read_to_string(&device_path).with_context(|| format!("read {}", device_path.display()))?;
+    let device: DeviceConfig =
+        serde_json::from_str(&raw).with_context(|| format!("parse {}", device_path.display()))?;
+    for strip in device.strips.iter() {
+        if let Some(led) = strip.leds.iter().find(|led| led.index >= strip.led_count) {
+            bail!("{}: strip `{}` has LED index {} but ledCount {}", device_path.display(), strip.id, led.index, strip.led_count);
+        }
+    }
+    let renderer = DeviceRenderer::new(device, args.theme, args.scale).with_context(|| device_path.display().to_string())?;
+
+    // The preview page creates one machine for all strips (LEDEffectPreviewPage.tsx).
+    let mut config: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(&args.config).with_context(|| format!("--config is not a JSON object: {}", args.config))?;
+    let led_count = renderer.led_count();
+    if let Some(given) = config.get("ledCount").filter(|v| v.as_u64() != Some(led_count as u64)) {
+        bail!("--config ledCount {given} does not match {device_id} ({led_count} LEDs)");
+    }
+    config.insert("ledCount".to_string(), led_count.into());
+    let mut set = serde_json::Map::new();
+    for assignment in args.set.iter() {
+        let (key, value) = parse_assignment(assignment)?;
+        set.insert(key.to_string(), value);
+    }
+
+    let runner = start_effect(frontend_root, &args.id, &config, &set)?;
+    let speed = runner.speed()?;
+    let frames = capture_frames(&runner, args.frames)?;
+    let images: Vec<Canvas> = frames.iter().map(|leds| renderer.draw(leds)).collect();
+
+    let out = match &args.out {
+        Some(path) => path.clone(),
+        None => frontend_root.join("build/effects").join(format!("{}-{device_id}.gif", args.id)),
+    };
+    if let Some(dir) = out.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+    }
+    let file = fs::File::create(&out).with_context(|| format!("create {}", out.display()))?;
+    let writer = std::io::BufWriter::new(file);
+    match out.extension().and_then(|e| e.to_str()) {
+        Some("gif") => write_gif(writer, &renderer, &images, speed),
+        Some("png") => write_sprite_sheet(writer, &renderer, &images, args.columns),
+        _ => bail!("--out must end in .gif or .png: {}", out.display()),
+    }
+    .with_context(|| format!("write {}", out.display()))?;
+
+    println!("[led-effects] wrote {} ({} frames, {}x{} px)", out.display(), images.len(), renderer.width, renderer.height);
+    Ok(())
+}
+
+/// Animated GIF, one frame per tick, looping, timed by the machine's `speed`.
+fn write_gif(writer: impl std::io::Write, renderer: &DeviceRenderer, images: &[Canvas], speed: f64) -> Result<()> {
+    let (width, height) = (u16::try_from(renderer.width)?, u16::try_from(renderer.height)?);
+    let mut encoder = gif::Encoder::new(writer, width, height, &[])?;
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+    let delay = (speed / 10.0).round().clamp(2.0, u16::MAX as f64) as u16;
+    for image in images.iter() {
+        let mut frame = gif::Frame::from_rgb_speed(width, height, &image.rgb, 10);
+        frame.delay = delay;
+        encoder.write_frame(&frame)?;
+    }
+    Ok(())
+}
+
+/// All frames on one PNG, left to right then top to bottom.
+fn write_sprite_sheet(writer: impl std::io::Write, renderer: &DeviceRenderer, images: &[Canvas], columns: usize) -> Result<()> {
+    let columns = columns.clamp(1, images.len().max(1));
+    let rows = images.len().div_ceil(columns).max(1);
+    let (w, h) = (renderer.width, renderer.height);
+    let mut sheet = Canvas::new(
+        columns * w + (columns - 1) * SHEET_GAP_PX,
+        rows * h + (rows - 1) * SHEET_GAP_PX,
+        renderer.palette.guard.0,
+    );
+    for (i, image) in images.iter().enumerate() {
+        let (x0, y0) = ((i % columns) * (w + SHEET_GAP_PX), (i / columns) * (h + SHEET_GAP_PX));
+        for y in 0..h {
+            let src = &image.rgb[y * w * 3..(y + 1) * w * 3];
+            let o = ((y0 + y) * sheet.width + x0) * 3;
+            sheet.rgb[o..o + w * 3].copy_from_slice(src);
+        }
+    }
+
+    let mut encoder = png::Encoder::new(writer, u32::try_from(sheet.width)?, u32::try_from(sheet.height)?);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&sheet.rgb)?;
+    Ok(())
+}
+
+/// `rrggbb rrggbb ...` for one LED buffer.
+fn frame_hex(frame: &[u8]) -> String {
+    frame
+        .chunks(3)
+        .map(|rgb| rgb.iter().map(|c| format!("{c:02x}")).collect::<String>())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Inverse of [`frame_hex`].
+fn parse_frame_hex(text: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    for pixel in text.split_whitespace() {
+        if pixel.len() != 6 {
+            bail!("`{pixel}` is not an rrggbb color");
+        }
+        for i in (0..6).step_by(2) {
+            let channel = u8::from_str_radix(&pixel[i..i + 2], 16).map_err(|_| anyhow!("`{pixel}` is not an rrggbb color"))?;
+            bytes.push(channel);
+        }
+    }
+    Ok(bytes)
+}
+
+/// `key=value` from `--set`; the value is JSON, or a plain string otherwise.
+fn parse_assignment(assignment: &str) -> Result<(&str, serde_json::Value)> {
+    let (key, raw) = assignment
+        .split_once('=')
+        .ok_or_else(|| anyhow!("--set expects KEY=VALUE, got `{assignment}`"))?;
+    let value = serde_json::from_str(raw).unwrap_or_else(|_| serde_json::Value::from(raw));
+    Ok((key.trim(), value))
+}
+
+/// LED channels as bytes; anything that is not an integer in 0..=255 is an error.
+fn frame_bytes(leds: &[f64]) -> Result<Vec<u8>> {
+    leds.iter()
+        .enumerate()
+        .map(|(i, &v)| {
+            if v.fract() == 0.0 && (0.0..=255.0).contains(&v) {
+                Ok(v as u8)
+            } else {
+                Err(anyhow!("LED {} channel {} is {v}, expected an integer 0-255", i / 3, ["r", "g", "b"][i % 3]))
+            }
+        })
+        .collect()
+}
+
+/// effect-runtime.ts and an effect.ts compiled into one plain-JS script.
+struct EffectBundle {
+    source: String,
+    /// `(path relative to the frontend root, first line in the bundle)`.
+    files: Vec<(String, usize)>,
+}
+
+impl EffectBundle {
+    /// Rewrite `eval_script:LINE:COL` frames of an engine stack to `file:LINE:COL`.
+    /// Frames of the one-line driver snippets (`<eval>`) are dropped.
+    fn map_stack(&self, stack: &str) -> String {
+        let mut lines = Vec::new();
+        for line in stack.lines() {
+            if line.trim_start().starts_with("at <eval>") {
+                continue;
+            }
+            let Some(start) = line.find("eval_script:") else {
+                lines.push(line.to_string());
+                continue;
+            };
+            let rest = &line[start + "eval_script:".len()..];
+            let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+            let Ok(bundle_line) = rest[..digits].parse::<usize>() else {
+                lines.push(line.to_string());
+                continue;
+            };
+            match self.files.iter().rev().find(|(_, first)| *first <= bundle_line) {
+                Some((file, first)) => {
+                    lines.push(format!("{}{file}:{}{}", &line[..start], bundle_line - first + 1, &rest[digits..]))
+                }
+                None => lines.push(line.to_string()),
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod map_stack_tests {
+    use super::EffectBundle;
+
+    #[test]
+    fn maps_bundle_lines_to_files() {
+        let bundle = EffectBundle {
+            source: String::new(),
+            files: vec![("effect-runtime.ts".into(), 1), ("rainbow/effect.ts".into(), 40)],
+        };
+        let stack = "    at hsv (eval_script:12:5)\n    at tick (eval_script:45:9)\n    at <eval> (<input>:1:1)\n    at native";
+        assert_eq!(
+            bundle.map_stack(stack),
+            "    at hsv (effect-runtime.ts:12:5)\n    at tick (rainbow/effect.ts:6:9)\n    at native"
+        );
+    }
+}
+
+/// Bundle effect-runtime.ts and `<id>/effect.ts` into one plain-JS script,
+/// the way `just compile-effects` does for the device.
+fn compile_effect(frontend_root: &Path, id: &str) -> Result<EffectBundle> {
+    let effects_dir = frontend_root.join(EFFECTS_DIR);
+    let runtime_path = effects_dir.join(EFFECT_RUNTIME_FILE);
+    let effect_path = effects_dir.join(id).join("effect.ts");
+    if !effect_path.is_file() {
+        bail!("unknown effect `{id}`: no {EFFECTS_DIR}/{id}/effect.ts");
+    }
+
+    let mut bundle = EffectBundle { source: String::new(), files: Vec::new() };
+    for path in [&runtime_path, &effect_path] {
+        let text = fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+        let js = strip_types(&text).with_context(|| format!("compile {}", path.display()))?;
+        let first_line = bundle.source.lines().count() + 1;
+        bundle.files.push((format!("{EFFECTS_DIR}/{}", path.strip_prefix(&effects_dir).unwrap_or(path).display()), first_line));
+        bundle.source.push_str(&js);
+        if !js.ends_with('\n') {
+            bundle.source.push('\n');
+        }
+    }
+    Ok(bundle)
+}
+
+/// TypeScript to plain JS by blanking type-only syntax (annotations, `as`,
+/// `!`, interfaces, type aliases, declarations, generics). Offsets and line
+/// numbers stay the same, so engine errors point into the .ts file.
+fn strip_types(text: &str) -> Result<String> {
+    let allocator = Allocator::default();
+    let ret = JsParser::new(&allocator, text, SourceType::ts()).parse();
+    if let Some(error) = ret.errors.first() {
+        bail!("{error}");
+    }
+
+    let mut visitor = TypeSpanVisitor { text, spans: Vec::new(), unsupported: None };
+    visitor.visit_program(&ret.program);
+    if let Some(reason) = visitor.unsupported {
+        bail!("unsupported TypeScript: {reason}");
+    }
+
+    let mut bytes = text.as_bytes().to_vec();
+    for span in visitor.spans {
+        for b in bytes[span.start as usize..span.end as usize].iter_mut() {
+            if *b != b'\n' {
+                *b = b' ';
+            }
+        }
+    }
+    // Whole bytes are blanked, so multi-byte characters never get split.
+    String::from_utf8(bytes).context("blanked source is not UTF-8")
+}
+
+/// Spans of type-only syntax.
+struct TypeSpanVisitor<'t> {
+    text: &'t str,
+    spans: Vec<Span>,
+    /// TypeScript that cannot be stripped to JS.
+    unsupported: Option<String>,
+}
+
+impl<'a> Visit<'a> for TypeSpanVisitor<'_> {
+    fn visit_statement(&mut self, it: &Statement<'a>) {
+        match it {
+            Statement::TSInterfaceDeclaration(_)
+            | Statement::TSTypeAliasDeclaration(_)
+            | Statement::TSGlobalDeclaration(_)
+            | Statement::TSModuleDeclaration(_) => self.spans.push(it.span()),
+            Statement::TSEnumDeclaration(e) => {
+                self.unsupported.get_or_insert_with(|| format!("enum `{}` needs a runtime object", e.id.name));
+            }
+            Statement::FunctionDeclaration(f) if f.declare || f.body.is_none() => self.spans.push(it.span()),
+            Statement::VariableDeclaration(v) if v.declare => self.spans.push(it.span()),
+            Statement::ClassDeclaration(c) if c.declare => self.spans.push(it.span()),
+            _ => walk::walk_statement(self, it),
+        }
+    }
+
+    fn visit_ts_type_annotation(&mut self, it: &TSTypeAnnotation<'a>) {
+        self.spans.push(it.span);
+    }
+
+    fn visit_ts_type_parameter_declaration(&mut self, it: &TSTypeParameterDeclaration<'a>) {
+        self.spans.push(it.span);
+    }
+
+    fn visit_ts_type_parameter_instantiation(&mut self, it: &TSTypeParameterInstantiation<'a>) {
+        self.spans.push(it.span);
+    }
+
+    fn visit_ts_as_expression(&mut self, it: &TSAsExpression<'a>) {
+        self.spans.push(Span::new(it.expression.span().end, it.span.end));
+        self.visit_expression(&it.expression);
+    }
+
+    fn visit_ts_satisfies_expression(&mut self, it: &TSSatisfiesExpression<'a>) {
+        self.spans.push(Span::new(it.expression.span().end, it.span.end));
+        self.visit_expression(&it.expression);
+    }
+
+    fn visit_ts_non_null_expression(&mut self, it: &TSNonNullExpression<'a>) {
+        self.spans.push(Span::new(it.expression.span().end, it.span.end));
+        self.visit_expression(&it.expression);
+    }
+
+    fn visit_formal_parameter(&mut self, it: &FormalParameter<'a>) {
+        // `config?: T` - the `?` sits between the binding and the annotation.
+        if it.optional {
+            let start = it.pattern.span().end;
+            if let Some(pos) = self.text[start as usize..it.span.end as usize].find('?') {
+                let at = start + pos as u32;
+                self.spans.push(Span::new(at, at + 1));
+            }
+        }
+        walk::walk_formal_parameter(self, it);
+    }
+
+    fn visit_class(&mut self, it: &Class<'a>) {
+        for implements in it.implements.iter() {
+            self.spans.push(implements.span);
+        }
+        walk::walk_class(self, it);
+    }
+}
+
+#[cfg(test)]
+mod strip_types_tests {
+    use super::strip_types;
+
+    #[test]
+    fn blanks_types_in_place() {
+        let src = "interface Cfg { hue: number }\nconst x: number = 1 as number;\nfunction f(a: Cfg): void {}\n";
+        let js = strip_types(src).unwrap();
+        assert_eq!(js.len(), src.len());
+        assert_eq!(js.lines().count(), src.lines().count());
+        assert!(!js.contains("number") && !js.contains("Cfg") && !js.contains("void"), "{js}");
+        assert!(js.contains("const x") && js.contains("= 1") && js.contains("function f(a"), "{js}");
+    }
+
+    #[test]
+    fn keeps_multibyte_text_intact() {
+        let src = "// 彩虹 🌈\nconst name: string = '红色';\ntype Hue = '色相';\n";
+        let js = strip_types(src).unwrap();
+        assert_eq!(js.len(), src.len());
+        assert!(js.starts_with("// 彩虹 🌈\nconst name"), "{js}");
+        assert!(js.contains("= '红色';") && !js.contains("string") && !js.contains("色相"), "{js}");
+    }
+
+    #[test]
+    fn rejects_enums() {
+        let err = strip_types("enum Mode { A, B }").unwrap_err();
+        assert!(err.to_string().contains("enum `Mode`"), "{err}");
+    }
+}
+
+/// One effect machine inside its own QuickJS runtime.
+struct EffectRunner {
+    context: Context,
+    _runtime: Runtime,
+    deadline: Rc<Cell<Instant>>,
+    bundle: EffectBundle,
+}
+
+impl EffectRunner {
+    /// Evaluate the compiled bundle; [`EffectRunner::create`] makes the machine.
+    fn new(bundle: EffectBundle) -> Result<Self> {
+        let runtime = Runtime::new().map_err(|e| anyhow!("start QuickJS: {e}"))?;
+        let deadline = Rc::new(Cell::new(Instant::now() + CALL_TIME_LIMIT));
+        let interrupt_deadline = deadline.clone();
+        runtime.set_interrupt_handler(Some(Box::new(move || Instant::now() > interrupt_deadline.get())));
+        let context = Context::full(&runtime).map_err(|e| anyhow!("create QuickJS context: {e}"))?;
+
+        let runner = Self { context, _runtime: runtime, deadline, bundle };
+        runner.eval::<()>(&runner.bundle.source)?;
+        Ok(runner)
+    }
+
+    /// Replace the machine with a fresh `createEffect(config)`.
+    fn create(&self, config: &serde_json::Value) -> Result<()> {
+        self.eval::<()>(&format!("globalThis.__machine = createEffect({config});"))
+            .with_context(|| format!("createEffect({config})"))
+    }
+
+    /// Back `makeBlank` buffers with a Float64Array, so NaN, fractional and
+    /// out-of-range writes stay visible instead of being wrapped by Uint8Array.
+    fn keep_raw_channels(&self) -> Result<()> {
+        self.eval("makeBlank = function (ledCount) { return new Float64Array(ledCount * 3); };")
+    }
+
+    fn status(&self) -> Result<String> {
+        self.eval("String(__machine.status)").context("read status")
+    }
+
+    fn led_count(&self) -> Result<f64> {
+        self.eval("Number(__machine.ledCount)").context("read ledCount")
+    }
+
+    /// Tick interval in ms.
+    fn speed(&self) -> Result<f64> {
+        self.eval("Number(__machine.speed)").context("read speed")
+    }
+
+    fn buffer_len(&self) -> Result<f64> {
+        self.eval("Number(__machine.leds.length)").context("read leds.length")
+    }
+
+    /// Evaluate `code`, turning JS exceptions into errors with message and stack.
+    fn eval<T>(&self, code: &str) -> Result<T>
+    where
+        T: for<'js> FromJs<'js>,
+    {
+        self.deadline.set(Instant::now() + CALL_TIME_LIMIT);
+        self.context.with(|ctx| {
+            ctx.eval::<T, _>(code).map_err(|error| {
+                if !error.is_exception() {
+                    return anyhow!("{error}");
+                }
+                let thrown = ctx.catch();
+                match thrown.as_exception() {
+                    Some(exception) => {
+                        let message = exception.message().unwrap_or_default();
+                        match exception.stack().filter(|s| !s.trim().is_empty()) {
+                            Some(stack) => anyhow!("{message}\n{}", self.bundle.map_stack(stack.trim_end())),
+                            None => anyhow!("{message}"),
+                        }
+                    }
+                    None => anyhow!("uncaught exception: {thrown:?}"),
+                }
+            })
+        })
+    }
+
+    /// Call a no-argument lifecycle method (`start`, `tick`, `pause`, ...).
+    fn call(&self, method: &str) -> Result<()> {
+        self.eval::<()>(&format!("__machine.{method}();")).with_context(|| format!("{method}()"))
+    }
+
+    fn apply(&self, op: &Op) -> Result<()> {
+        self.eval::<()>(&format!("{};", op.js("__machine"))).with_context(|| op.js("m"))
+    }
+
+    fn set_config(&self, key: &str, value: &serde_json::Value) -> Result<()> {
+        let key_json = serde_json::Value::from(key);
+        self.eval::<()>(&format!("__machine.setConfig({key_json}, {value});"))
+            .with_context(|| format!("setConfig({key_json}, {value})"))
+    }
+
+    /// The machine's `leds` buffer, as numbers so corrupt channels stay visible.
+    fn leds(&self) -> Result<Vec<f64>> {
+        self.eval("Array.prototype.slice.call(__machine.leds)").context("read leds")
+    }
+}
+
+fn locate_frontend_root(start: &Path) -> Result<PathBuf> {
+    let mut current = start.to_path_buf();
+    for _ in 0..5 {
+        if current.join(EFFECTS_DIR).is_dir() {
+            return Ok(current);
+        }
+        if current.join("frontend").join(EFFECTS_DIR).is_dir() {
+            return Ok(current.join("frontend"));
+        }
+        if !current.pop() {
+            break;
+        }
+    }
+
+    Err(anyhow!(
+        "Could not locate frontend root from: {} (expected to find frontend/{EFFECTS_DIR})",
+        start.display()
+    ))
+}
@@ -86,6 +86,29 @@ export function isWebfError(x: unknown): x is WebfResponse<never> {
   return typeof x === 'object' && x !== null && 'error' in x;
 }
 
+/** Error thrown by unwrapWebfResponse for { error: { code, message } } responses. */
+export class WebfModuleError extends Error {
+  constructor(
+    message: string,
+    readonly code: number,
+  ) {
+    super(message);
+    this.name = 'WebfModuleError';
+  }
+}
+
+/**
+ * Unwrap a native module response: returns `result`, throws WebfModuleError on `error`.
+ * Wrappers should read responses only through this helper.
+ */
+export function unwrapWebfResponse<T>(x: unknown): T {
+  if (isWebfError(x)) {
+    const { code, message } = x.error!;
+    throw new WebfModuleError(message, code);
+  }
+  return (x as WebfResponse<T>).result as T;
+}
+
 /** Check if WebF bridge (window.webf.invokeModule) is available. */
 export function isWebfAvailable(): boolean {
   if (typeof globalThis === 'undefined') return false;